@@ -0,0 +1,96 @@
+use std::{env, fs, path::Path, process};
+
+use random_pedersen::{
+    identity::NodeIdentity,
+    store::StoreKeyring,
+    tls::{generate_ca, generate_node_certificate, get_certificate_name},
+};
+
+const USAGE: &str = "usage: keygen <signing key file>\n       keygen tls <output dir> <number of nodes> [host...]\n       keygen store-key <key id>";
+
+// generates the PKCS#8 signing key of a node and prints its public key for the peer table,
+// a local CA and the TLS certificates of the nodes, or a key sealing the session store
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.as_slice() {
+        [command, dir, num_nodes, hosts @ ..] if command == "tls" => match num_nodes.parse() {
+            Ok(num_nodes) => generate_certificates(Path::new(dir), num_nodes, hosts),
+            Err(_) => usage(),
+        },
+        [command, key_id] if command == "store-key" => generate_store_key(key_id),
+        [path] if path != "tls" && path != "store-key" => generate_signing_key(path),
+        _ => usage(),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn generate_signing_key(path: &str) -> Result<(), String> {
+    let document = NodeIdentity::generate()
+        .map_err(|err| format!("failed to generate signing key: {}", err))?;
+    fs::write(path, &document).map_err(|err| format!("failed to write signing key: {}", err))?;
+
+    let identity = NodeIdentity::from_pkcs8(&document).unwrap();
+    println!("{}", hex::encode(identity.public_key()));
+    Ok(())
+}
+
+// prints the key in the `key_id:hex` format of the store key file
+fn generate_store_key(key_id: &str) -> Result<(), String> {
+    if key_id.is_empty() || key_id.contains(':') {
+        return Err(format!("invalid key id {}", key_id));
+    }
+    let key = StoreKeyring::generate(key_id)
+        .map_err(|err| format!("failed to generate store key: {}", err))?;
+    println!("{}", key.as_str());
+    Ok(())
+}
+
+// writes `ca.pem` and the `node-{id}.pem` certificates and `node-{id}.key` keys of the nodes,
+// valid for their certificate name, localhost and the given hosts
+fn generate_certificates(dir: &Path, num_nodes: u16, hosts: &[String]) -> Result<(), String> {
+    let write = |name: String, content: String| {
+        fs::write(dir.join(&name), content)
+            .map_err(|err| format!("failed to write {}: {}", name, err))
+    };
+    let generation_error = |err: rcgen::Error| format!("failed to generate certificate: {}", err);
+
+    fs::create_dir_all(dir)
+        .map_err(|err| format!("failed to create {}: {}", dir.display(), err))?;
+
+    let ca = generate_ca().map_err(generation_error)?;
+    write(
+        "ca.pem".to_string(),
+        ca.serialize_pem().map_err(generation_error)?,
+    )?;
+
+    let hosts: Vec<String> = ["localhost".to_string(), "127.0.0.1".to_string()]
+        .into_iter()
+        .chain(hosts.iter().cloned())
+        .collect();
+    for node_id in 1..num_nodes + 1 {
+        let certificate = generate_node_certificate(node_id, &hosts).map_err(generation_error)?;
+        let name = get_certificate_name(node_id);
+        write(
+            format!("{}.pem", name),
+            certificate
+                .serialize_pem_with_signer(&ca)
+                .map_err(generation_error)?,
+        )?;
+        write(
+            format!("{}.key", name),
+            certificate.serialize_private_key_pem(),
+        )?;
+    }
+
+    println!("{}", dir.display());
+    Ok(())
+}
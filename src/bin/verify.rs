@@ -0,0 +1,48 @@
+use std::{env, fs, io, io::Read, process};
+
+use random_pedersen::{
+    transcript::{extract_random, verify_transcript, OUTPUT_LENGTH},
+    types::SessionProof,
+};
+
+// verifies a session transcript, as returned by the proof endpoint, from a file or stdin
+fn main() {
+    let mut transcript = String::new();
+    let result = match env::args().nth(1) {
+        Some(path) => fs::read_to_string(path).map(|content| transcript = content),
+        None => io::stdin().read_to_string(&mut transcript).map(|_| ()),
+    };
+    if let Err(err) = result {
+        eprintln!("failed to read transcript: {}", err);
+        process::exit(2);
+    }
+
+    let proof: SessionProof = match serde_json::from_str(&transcript) {
+        Ok(proof) => proof,
+        Err(err) => {
+            eprintln!("failed to parse transcript: {}", err);
+            process::exit(2);
+        }
+    };
+
+    match verify_transcript(&proof) {
+        Ok(opening) => {
+            let output = extract_random(
+                &opening,
+                proof.commitment_id,
+                &proof.node_ids,
+                OUTPUT_LENGTH,
+            );
+            println!(
+                "verified commitment {} random {} output {}",
+                proof.commitment_id,
+                serde_json::to_string(&opening.value).unwrap(),
+                hex::encode(output)
+            );
+        }
+        Err(err) => {
+            eprintln!("verification failed: {}", err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+use tracing::{event, Level};
+
+use super::state::Role;
+
+// most recent records kept in memory for queries, whether or not the log is written to a file
+const MAX_RECENT_RECORDS: usize = 10_000;
+// size of the log file before it is rotated, the previous file is kept with a `.1` extension
+const MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
+// records returned by a query by default
+const DEFAULT_QUERY_LIMIT: usize = 1_000;
+
+/// Reason a session entry left the session store.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum EvictionCause {
+    /// The opening was revealed, recorded on the first reveal rather than on the purge after the
+    /// reveal retention.
+    Revealed,
+    /// The time to live or the reveal deadline of the session elapsed before the reveal.
+    Expired,
+    /// The opening wasn't read within the time to idle.
+    Idle,
+    /// The store was full and the entry was evicted to make room.
+    Capacity,
+    /// The entry couldn't be decoded from the store and was dropped.
+    Corrupted,
+    /// The dealer released the co-commitment, left out of its draw once the quorum was reached.
+    Released,
+}
+
+impl EvictionCause {
+    /// Whether the opening was withheld, i.e. the requester never asked for its reveal.
+    pub fn is_withheld(self) -> bool {
+        matches!(self, EvictionCause::Expired | EvictionCause::Idle)
+    }
+}
+
+/// Audit record of a session entry leaving the session store.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AuditRecord {
+    pub commitment_id: u128,
+    pub role: Role,
+    /// Party the opening was committed for, `node:{id}` for the dealer of a co-commitment and
+    /// `client:{ip}` for the client of a dealer commitment
+    pub requester: String,
+    pub cause: EvictionCause,
+    /// Unix time the entry was stored, in milliseconds
+    pub stored_at_ms: u64,
+    /// Unix time the entry was revealed or left the store, in milliseconds
+    pub evicted_at_ms: u64,
+}
+
+/// Outcome of the sessions of a requester.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RequesterStats {
+    pub sessions: u64,
+    pub revealed: u64,
+    pub expired: u64,
+    pub idle: u64,
+    pub capacity: u64,
+    #[serde(default)]
+    pub corrupted: u64,
+    #[serde(default)]
+    pub released: u64,
+    /// Share of the sessions whose opening was withheld until it expired
+    pub withheld_rate: f64,
+}
+
+impl RequesterStats {
+    fn add(&mut self, cause: EvictionCause) {
+        self.sessions += 1;
+        match cause {
+            EvictionCause::Revealed => self.revealed += 1,
+            EvictionCause::Expired => self.expired += 1,
+            EvictionCause::Idle => self.idle += 1,
+            EvictionCause::Capacity => self.capacity += 1,
+            EvictionCause::Corrupted => self.corrupted += 1,
+            EvictionCause::Released => self.released += 1,
+        }
+        self.withheld_rate = (self.expired + self.idle) as f64 / self.sessions as f64;
+    }
+}
+
+/// Filter of an audit log query, matching all the records by default.
+#[serde_as]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AuditQuery {
+    pub requester: Option<String>,
+    // query strings don't carry 128-bit integers natively
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    pub commitment_id: Option<u128>,
+    pub cause: Option<EvictionCause>,
+    /// Maximum number of records, the most recent ones are returned
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, record: &AuditRecord) -> bool {
+        self.requester
+            .as_ref()
+            .is_none_or(|requester| *requester == record.requester)
+            && self
+                .commitment_id
+                .is_none_or(|commitment_id| commitment_id == record.commitment_id)
+            && self.cause.is_none_or(|cause| cause == record.cause)
+    }
+}
+
+// log file being appended to, with its size to rotate it
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    len: u64,
+}
+
+impl LogFile {
+    fn open(path: &Path) -> Result<Self, io::Error> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let len = file.metadata()?.len();
+        Ok(LogFile {
+            path: path.to_path_buf(),
+            file,
+            len,
+        })
+    }
+
+    // appends a line, first moving the file to its rotated path once it reached the maximum size
+    fn append(&mut self, line: &str, max_bytes: u64) -> Result<(), io::Error> {
+        if self.len > 0 && self.len + line.len() as u64 + 1 > max_bytes {
+            std::fs::rename(&self.path, rotated_path(&self.path))?;
+            *self = LogFile::open(&self.path)?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.len += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+// path of the previous log file, replaced on every rotation
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+/// Local log of the session entries leaving the session store.
+///
+/// Records are appended as JSON lines to the log file if configured, which is rotated once it
+/// reaches 64 MiB, and the most recent ones are kept in memory for queries. The outcome of the
+/// sessions is aggregated per requester, including the records already in the current and the
+/// rotated file when the log is opened.
+pub struct AuditLog {
+    file: Option<Mutex<LogFile>>,
+    max_file_bytes: u64,
+    recent: Mutex<VecDeque<AuditRecord>>,
+    stats: Mutex<HashMap<String, RequesterStats>>,
+}
+
+impl AuditLog {
+    pub fn open(path: Option<&Path>) -> Result<Self, io::Error> {
+        let mut stats: HashMap<String, RequesterStats> = HashMap::new();
+        let mut recent = VecDeque::new();
+        let file = match path {
+            Some(path) => {
+                let mut records = read_records(&rotated_path(path))?;
+                records.extend(read_records(path)?);
+                for record in &records {
+                    stats
+                        .entry(record.requester.clone())
+                        .or_default()
+                        .add(record.cause);
+                }
+                let skip = records.len().saturating_sub(MAX_RECENT_RECORDS);
+                recent.extend(records.into_iter().skip(skip));
+                Some(Mutex::new(LogFile::open(path)?))
+            }
+            None => None,
+        };
+        Ok(AuditLog {
+            file,
+            max_file_bytes: MAX_FILE_BYTES,
+            recent: Mutex::new(recent),
+            stats: Mutex::new(stats),
+        })
+    }
+
+    /// Appends the record of an entry that left the store now.
+    pub fn record(
+        &self,
+        commitment_id: u128,
+        role: Role,
+        requester: &str,
+        cause: EvictionCause,
+        stored_at_ms: u64,
+    ) {
+        let record = AuditRecord {
+            commitment_id,
+            role,
+            requester: requester.to_string(),
+            cause,
+            stored_at_ms,
+            evicted_at_ms: now_ms(),
+        };
+        event!(
+            Level::DEBUG,
+            "cache::audit::record {} {} {} {:?}",
+            commitment_id,
+            role,
+            requester,
+            cause
+        );
+
+        self.stats
+            .lock()
+            .unwrap()
+            .entry(record.requester.clone())
+            .or_default()
+            .add(cause);
+
+        if let Some(file) = &self.file {
+            let written = serde_json::to_string(&record)
+                .map_err(io::Error::from)
+                .and_then(|line| file.lock().unwrap().append(&line, self.max_file_bytes));
+            if let Err(err) = written {
+                event!(Level::ERROR, "cache::audit::record {}", err);
+            }
+        }
+
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == MAX_RECENT_RECORDS {
+            recent.pop_front();
+        }
+        recent.push_back(record);
+    }
+
+    /// Returns the most recent records matching the query, oldest first, out of the records kept
+    /// in memory.
+    pub fn records(&self, query: &AuditQuery) -> Vec<AuditRecord> {
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+        let mut records: Vec<AuditRecord> = self
+            .recent
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|record| query.matches(record))
+            .take(limit)
+            .cloned()
+            .collect();
+        records.reverse();
+        records
+    }
+
+    /// Returns the outcome of the sessions of every requester.
+    pub fn requester_stats(&self) -> HashMap<String, RequesterStats> {
+        self.stats.lock().unwrap().clone()
+    }
+}
+
+// reads the records of the log file, a missing file has no records and corrupted lines are skipped
+fn read_records(path: &Path) -> Result<Vec<AuditRecord>, io::Error> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(record) = serde_json::from_str(&line?) {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// Unix time in milliseconds.
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn test_audit_log() {
+        let path = temp_dir().join(format!("random-pedersen-test-audit-{}", Uuid::new_v4()));
+
+        let log = AuditLog::open(Some(&path)).unwrap();
+        log.record(
+            1,
+            Role::Dealer,
+            "client:127.0.0.1",
+            EvictionCause::Revealed,
+            0,
+        );
+        log.record(
+            2,
+            Role::Dealer,
+            "client:127.0.0.1",
+            EvictionCause::Expired,
+            0,
+        );
+        log.record(2, Role::CoCommitter, "node:1", EvictionCause::Idle, 0);
+        log.record(3, Role::CoCommitter, "node:1", EvictionCause::Capacity, 0);
+        log.record(4, Role::CoCommitter, "node:1", EvictionCause::Released, 0);
+        drop(log);
+
+        // the records and the stats survive a restart
+        let log = AuditLog::open(Some(&path)).unwrap();
+        let stats = log.requester_stats();
+        assert_eq!(stats["client:127.0.0.1"].sessions, 2);
+        assert_eq!(stats["client:127.0.0.1"].withheld_rate, 0.5);
+        assert_eq!(stats["node:1"].idle, 1);
+        assert_eq!(stats["node:1"].capacity, 1);
+        assert_eq!(stats["node:1"].released, 1);
+        assert_eq!(stats["node:1"].withheld_rate, 1.0 / 3.0); // released co-commitments aren't withheld
+
+        let records = log.records(&AuditQuery {
+            commitment_id: Some(2),
+            ..AuditQuery::default()
+        });
+        assert_eq!(records.len(), 2);
+        let records = log.records(&AuditQuery {
+            requester: Some("node:1".to_string()),
+            limit: Some(1),
+            ..AuditQuery::default()
+        });
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].commitment_id, 4);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_audit_log_rotation() {
+        let path = temp_dir().join(format!("random-pedersen-test-audit-{}", Uuid::new_v4()));
+
+        let mut log = AuditLog::open(Some(&path)).unwrap();
+        log.max_file_bytes = 200;
+        for commitment_id in 0..4 {
+            log.record(
+                commitment_id,
+                Role::Dealer,
+                "node:1",
+                EvictionCause::Revealed,
+                0,
+            );
+        }
+        // a record takes about 130 bytes, so every file holds a single one
+        assert_eq!(read_records(&path).unwrap().len(), 1);
+        assert_eq!(read_records(&rotated_path(&path)).unwrap().len(), 1);
+        assert_eq!(log.records(&AuditQuery::default()).len(), 4);
+        drop(log);
+
+        // the stats and the recent records of a restart only come from the remaining files
+        let log = AuditLog::open(Some(&path)).unwrap();
+        assert_eq!(log.requester_stats()["node:1"].sessions, 2);
+        let records = log.records(&AuditQuery::default());
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| record.commitment_id)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+
+        std::fs::remove_file(rotated_path(&path)).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1 +1,3 @@
+pub mod audit;
 pub mod state;
+pub mod store;
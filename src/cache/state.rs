@@ -1,28 +1,174 @@
-use moka::future::Cache;
+use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
-use uuid::Uuid;
+use std::{fmt, sync::Arc, time::Duration};
 
-use crate::utils::commitment::{Commitment, Opening};
+use super::{
+    audit::AuditLog,
+    store::{create_session_store, SessionStore},
+};
+use crate::utils::{
+    commitment::{Commitment, Opening, OpeningValue},
+    config::{NodeConfig, RandomMode},
+    errors::ConfigError,
+    identity::NodeIdentity,
+    peers::{create_peer_client, get_peers},
+};
+
+/// Role of a node in a session, a node can hold both the dealer and the co-committer entry of a session.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    Dealer,
+    CoCommitter,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Dealer => write!(f, "dealer"),
+            Role::CoCommitter => write!(f, "co-committer"),
+        }
+    }
+}
 
 #[allow(dead_code)]
 #[derive(Clone)]
 pub struct CommittedRandomData {
     pub commitment: Commitment,
     pub opening: Opening,
+    // dealer commitment the co-commitment is bound to, none for the dealer's own commitment
+    pub co_commitment: Option<CoCommitment>,
+    // reveal window of the session, overriding the time to live and idle of the node
+    pub reveal_deadline: Option<Duration>,
+    // party the opening was committed for, `client:{ip}` or `node:{id}`
+    pub requester: String,
+    // hash of the secret proven by the reveal requests of the session, if the client set one
+    pub auth_hash: Option<Vec<u8>>,
+}
+
+// dealer request a co-commitment was made for, and the response returned to the dealer
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CoCommitment {
+    pub dealer_commitment: CommitmentForRandom,
+    pub response: CommitmentForRandom,
+}
+
+// request for the opening of a node in a session
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RevealRequest {
+    pub commitment_id: u128,
+    // secret whose SHA-256 hash was set as the auth hash of the session, empty for sessions without
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub auth_proof: Vec<u8>,
+    // commitment the opening is expected to open, the reveal is rejected if the node holds another
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commitment: Option<Vec<u8>>,
+}
+
+// request of the dealer releasing the co-commitment of a peer left out of the draw
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ReleaseRequest {
+    pub node_id: u16,
+    pub commitment_id: u128,
+    // signature of the dealer over its node id and the commitment id
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signature: Vec<u8>,
+}
+
+// request finalizing the session proof on the dealer, the body is optional for sessions without an
+// auth hash
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProofRequest {
+    // secret of the session auth hash, forwarded to the participants to reveal their openings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub auth_proof: Vec<u8>,
 }
 
+// opening revealed by a node, tagged with the node and the session it belongs to
 #[derive(Serialize, Deserialize, Clone)]
-pub struct CommittedRandom {
+pub struct RevealResponse {
+    pub node_id: u16,
+    pub commitment_id: u128,
     pub commitment: Vec<u8>,
     pub opening: Vec<u8>,
+    // signature of the revealing node over its node id, the commitment id, commitment and opening
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signature: Vec<u8>,
+    // role the opening was committed in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<Role>,
+    // session output, available on the dealer once the session proof is complete
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Vec<u8>>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct CommitmentForRandom {
     pub node_id: u16,
     pub commitment_id: u128,
     pub commitment: Vec<u8>,
+    // range proof of the node's own contribution, empty in scalar mode
+    #[serde(default)]
+    pub range_proof: Vec<u8>,
+    // proof of knowledge of the opening of the node's own commitment
+    #[serde(default)]
+    pub knowledge_proof: Vec<u8>,
+    // signature of the node over its node id, the commitment id and commitment
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signature: Vec<u8>,
+    // reveal window of the session requested from the dealer, in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reveal_deadline: Option<u64>,
+    // SHA-256 hash of the secret the reveal requests of the session have to prove, requested from
+    // the dealer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_hash: Option<Vec<u8>>,
+    // mode of the session set by the dealer, every contribution is generated and verified in it
+    #[serde(default)]
+    pub random_mode: RandomMode,
+}
+
+#[cfg(test)]
+impl CommitmentForRandom {
+    // commitment of a node proven with the given opening, in the mode of the opening and without
+    // signature nor session parameters
+    pub(crate) fn for_test(
+        node_id: u16,
+        commitment_id: u128,
+        commitment: &Commitment,
+        opening: &Opening,
+    ) -> Self {
+        let (range_proof, random_mode) = match opening.value {
+            OpeningValue::Integer(_) => (
+                opening.prove_range(commitment_id, node_id).unwrap(),
+                RandomMode::Integer,
+            ),
+            OpeningValue::Scalar(_) => (Vec::new(), RandomMode::Scalar),
+        };
+        CommitmentForRandom {
+            node_id,
+            commitment_id,
+            commitment: commitment.to_bytes(),
+            range_proof,
+            knowledge_proof: opening.prove_knowledge(commitment_id, node_id).unwrap(),
+            signature: Vec::new(),
+            reveal_deadline: None,
+            auth_hash: None,
+            random_mode,
+        }
+    }
+}
+
+// optional parameters of a commit-random request
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct CommitRandomRequest {
+    // reveal window of the session in seconds, bounded by the maximum of every node
+    #[serde(default)]
+    pub reveal_deadline: Option<u64>,
+    // SHA-256 hash of a random secret, the openings of the session are then only revealed to
+    // requests proving the secret
+    #[serde(default)]
+    pub auth_hash: Option<Vec<u8>>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -30,24 +176,95 @@ pub struct CommitmentForRandoms {
     pub commitment_id: u128,
     pub commitment: Vec<u8>,
     pub node_ids: Vec<u16>,
-    pub dealer_id: u16
+    pub dealer_id: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RevealedRandom {
+    pub node_id: u16,
+    pub commitment: Vec<u8>,
+    pub opening: Vec<u8>,
+    // signature of the reveal, as returned by the node
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signature: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SessionProof {
+    pub commitment_id: u128,
+    pub dealer_id: u16,
+    pub node_ids: Vec<u16>,
+    // aggregated commitment as returned by commit-random
+    pub commitment: Vec<u8>,
+    // dealer commitment followed by peers co-commitments
+    pub commitments: Vec<CommitmentForRandom>,
+    pub openings: Vec<RevealedRandom>,
+    // final random, available once all openings are revealed
+    pub random: Option<OpeningValue>,
+    // uniform output extracted from the aggregated opening
+    #[serde(default)]
+    pub output: Option<Vec<u8>>,
+    // mode of the session, every recorded commitment and opening has to be in it
+    #[serde(default)]
+    pub random_mode: RandomMode,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DerivedRandoms {
+    pub commitment_id: u128,
+    pub label: String,
+    pub values: Vec<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DerivedStream {
+    pub commitment_id: u128,
+    pub label: String,
+    pub offset: u64,
+    pub bytes: Vec<u8>,
 }
 
 #[allow(dead_code)]
 #[derive(Clone)]
 pub struct AppState {
-    pub cache: Cache<Uuid, CommittedRandomData>,
-}
-
-pub fn create_state() -> AppState {
-    let cache = Cache::builder()
-        // Max 10,000 entries
-        .max_capacity(10_000)
-        // Time to live (TTL): 30 minutes
-        .time_to_live(Duration::from_secs(30 * 60))
-        // Time to idle (TTI):  5 minutes
-        .time_to_idle(Duration::from_secs(5 * 60))
-        // Create the cache.
-        .build();
-    AppState { cache }
+    pub config: Arc<NodeConfig>,
+    pub identity: Option<Arc<NodeIdentity>>,
+    pub peer_client: Client,
+    // entries are keyed by session and role, so the dealer and co-committer openings of a node don't
+    // collide, transcripts of the sessions dealt by the node are kept in the same store
+    pub cache: Arc<dyn SessionStore>,
+    // entries leaving the cache, revealed or withheld
+    pub audit: Arc<AuditLog>,
+}
+
+impl AppState {
+    // signs the message with the node's key, messages are left unsigned without a key
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        match &self.identity {
+            Some(identity) => identity.sign(message),
+            None => Vec::new(),
+        }
+    }
+}
+
+pub fn create_state(config: NodeConfig) -> Result<AppState, ConfigError> {
+    let audit = Arc::new(
+        AuditLog::open(config.audit_log_path.as_deref())
+            .map_err(|err| ConfigError::AuditLog(err.to_string()))?,
+    );
+    let cache = create_session_store(&config, config.session_limits(), audit.clone())
+        .map_err(|err| ConfigError::Store(err.to_string()))?;
+    // a public key that can't be decoded is a config error rather than a node without a key
+    for peer in get_peers(&config) {
+        peer.decode_public_key()?;
+    }
+    let peer_client = create_peer_client(&config)?;
+    let identity = config.identity()?.map(Arc::new);
+    Ok(AppState {
+        config: Arc::new(config),
+        identity,
+        peer_client,
+        cache,
+        audit,
+    })
 }
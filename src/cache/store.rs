@@ -0,0 +1,1705 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+use moka::{
+    future::Cache,
+    notification::RemovalCause,
+    ops::compute::{CompResult, Op},
+    Expiry,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{event, Level};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use super::{
+    audit::{now_ms, AuditLog, EvictionCause},
+    state::{CoCommitment, CommittedRandomData, Role, SessionProof},
+};
+use crate::utils::{
+    commitment::{Commitment, Opening},
+    config::{NodeConfig, StoreBackend},
+    errors::{CacheError, CommitmentError},
+    sealing::{SealedOpening, StoreKeyring},
+};
+
+/// Key of a session entry, a node holds at most one opening per session and role.
+pub type SessionKey = (Uuid, Role);
+
+/// Update of a stored session transcript, run again if the transcript changed concurrently.
+pub type ProofUpdate = Arc<dyn Fn(&mut SessionProof) + Send + Sync>;
+
+/// Capacity and expiration of the session entries, shared by all the backends.
+///
+/// Entries with a reveal deadline expire once it has elapsed since they were stored, regardless
+/// of the time to live and idle. Revealed entries expire once the reveal retention has elapsed
+/// since their first reveal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionLimits {
+    pub max_capacity: u64,
+    /// Entries expire this long after they are stored
+    pub time_to_live: Duration,
+    /// Entries expire this long after they are last read
+    pub time_to_idle: Duration,
+    /// Revealed entries are kept this long for repeated reveals, then purged
+    pub reveal_retention: Duration,
+    /// Transcripts of the sessions dealt by the node are kept this long after they are stored
+    pub proof_retention: Duration,
+}
+
+impl SessionLimits {
+    // an expired entry is idle unless its time to live or reveal deadline elapsed
+    fn expiration_cause(&self, reveal_deadline: Option<Duration>, age: Duration) -> EvictionCause {
+        if reveal_deadline.is_some() || age >= self.time_to_live {
+            EvictionCause::Expired
+        } else {
+            EvictionCause::Idle
+        }
+    }
+}
+
+// entries expire after their reveal deadline, or after the time to live or idle of the node, and
+// after the reveal retention once revealed
+struct SessionExpiry {
+    limits: SessionLimits,
+}
+
+impl Expiry<SessionKey, StoredSession> for SessionExpiry {
+    fn expire_after_create(
+        &self,
+        _key: &SessionKey,
+        value: &StoredSession,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(
+            value
+                .data
+                .reveal_deadline
+                .unwrap_or(self.limits.time_to_live.min(self.limits.time_to_idle)),
+        )
+    }
+
+    fn expire_after_read(
+        &self,
+        _key: &SessionKey,
+        value: &StoredSession,
+        read_at: Instant,
+        duration_until_expiry: Option<Duration>,
+        last_modified_at: Instant,
+    ) -> Option<Duration> {
+        if value.data.reveal_deadline.is_some() || value.revealed_at_ms.is_some() {
+            return duration_until_expiry;
+        }
+        let age = read_at.saturating_duration_since(last_modified_at);
+        Some(
+            self.limits
+                .time_to_idle
+                .min(self.limits.time_to_live.saturating_sub(age)),
+        )
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &SessionKey,
+        value: &StoredSession,
+        _updated_at: Instant,
+        duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        // entries are only updated when they are revealed
+        match value.revealed_at_ms {
+            Some(_) => Some(self.limits.reveal_retention),
+            None => duration_until_expiry,
+        }
+    }
+}
+
+/// Storage of the unrevealed openings of a node, and of the transcripts of the sessions it dealt.
+///
+/// Transcripts share the capacity of the openings and expire after the proof retention.
+pub trait SessionStore: Send + Sync {
+    /// Stores the entry unless the key is already taken, stored openings are never overwritten.
+    fn insert(
+        &self,
+        key: SessionKey,
+        data: CommittedRandomData,
+    ) -> BoxFuture<'_, Result<(), CacheError>>;
+
+    /// Reads the entry, resetting its idle timeout.
+    fn get(&self, key: SessionKey) -> BoxFuture<'_, Option<CommittedRandomData>>;
+
+    /// Moves the entry to the revealed state and reads it.
+    ///
+    /// Repeated reveals return the same entry until the reveal retention has elapsed since the
+    /// first one, then the entry is purged.
+    fn reveal(&self, key: SessionKey) -> BoxFuture<'_, Option<CommittedRandomData>>;
+
+    /// Whether the entry is stored, revealed or not, without resetting its idle timeout.
+    fn contains(&self, key: SessionKey) -> BoxFuture<'_, bool>;
+
+    /// Removes an unrevealed entry committed for the requester, returning whether it was removed.
+    ///
+    /// Released entries are audited as released rather than withheld.
+    fn release(&self, key: SessionKey, requester: String) -> BoxFuture<'_, bool>;
+
+    /// Stores the transcript of a session unless one is already stored for it.
+    fn insert_proof(&self, proof: SessionProof) -> BoxFuture<'_, Result<(), CacheError>>;
+
+    /// Reads the transcript of a session.
+    fn get_proof(&self, commitment_id: Uuid) -> BoxFuture<'_, Option<SessionProof>>;
+
+    /// Updates the transcript of a session atomically and returns the updated transcript.
+    fn update_proof(
+        &self,
+        commitment_id: Uuid,
+        update: ProofUpdate,
+    ) -> BoxFuture<'_, Option<SessionProof>>;
+}
+
+/// Opens the session store configured for the node, entries leaving it are recorded in the audit log.
+pub fn create_session_store(
+    config: &NodeConfig,
+    limits: SessionLimits,
+    audit: Arc<AuditLog>,
+) -> Result<Arc<dyn SessionStore>, CacheError> {
+    match (config.session_store, &config.session_store_path) {
+        (StoreBackend::Sled, Some(path)) => {
+            let keyring = config
+                .store_keyring()
+                .map_err(|err| CacheError::Storage(err.to_string()))?
+                .ok_or(CacheError::Storage("missing store key".to_string()))?;
+            Ok(Arc::new(SledStore::open(path, limits, keyring, audit)?))
+        }
+        (StoreBackend::Sled, None) => Err(CacheError::Storage(
+            "missing session store path".to_string(),
+        )),
+        (StoreBackend::Memory, _) => Ok(Arc::new(MemoryStore::new(limits, audit))),
+    }
+}
+
+// entry of the in-memory store, with the times it was stored and revealed for the audit log
+#[derive(Clone)]
+struct StoredSession {
+    data: CommittedRandomData,
+    stored_at_ms: u64,
+    revealed_at_ms: Option<u64>,
+}
+
+/// In-memory session store, entries are lost on restart.
+pub struct MemoryStore {
+    cache: Cache<SessionKey, StoredSession>,
+    proofs: Cache<Uuid, SessionProof>,
+    audit: Arc<AuditLog>,
+}
+
+impl MemoryStore {
+    pub fn new(limits: SessionLimits, audit: Arc<AuditLog>) -> Self {
+        let listener_audit = audit.clone();
+        let listener = move |key: Arc<SessionKey>, value: StoredSession, cause: RemovalCause| {
+            // revealed entries are audited on their first reveal
+            if value.revealed_at_ms.is_some() {
+                return;
+            }
+            let cause = match cause {
+                RemovalCause::Size => EvictionCause::Capacity,
+                RemovalCause::Expired => limits.expiration_cause(
+                    value.data.reveal_deadline,
+                    Duration::from_millis(now_ms().saturating_sub(value.stored_at_ms)),
+                ),
+                // entries are only replaced by their reveal, and audited when they are released
+                RemovalCause::Explicit | RemovalCause::Replaced => return,
+            };
+            listener_audit.record(
+                key.0.as_u128(),
+                key.1,
+                &value.data.requester,
+                cause,
+                value.stored_at_ms,
+            );
+        };
+        let cache = Cache::builder()
+            .max_capacity(limits.max_capacity)
+            .expire_after(SessionExpiry { limits })
+            .eviction_listener(listener)
+            .build();
+        let proofs = Cache::builder()
+            .max_capacity(limits.max_capacity)
+            .time_to_live(limits.proof_retention)
+            .build();
+        MemoryStore {
+            cache,
+            proofs,
+            audit,
+        }
+    }
+
+    /// Runs the pending maintenance of the cache, such as the eviction of expired entries.
+    pub async fn run_pending_tasks(&self) {
+        self.cache.run_pending_tasks().await
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn insert(
+        &self,
+        key: SessionKey,
+        data: CommittedRandomData,
+    ) -> BoxFuture<'_, Result<(), CacheError>> {
+        Box::pin(async move {
+            let session = StoredSession {
+                data,
+                stored_at_ms: now_ms(),
+                revealed_at_ms: None,
+            };
+            let entry = self.cache.entry(key).or_insert(session).await;
+            if !entry.is_fresh() {
+                return Err(CacheError::Taken);
+            }
+            Ok(())
+        })
+    }
+
+    fn get(&self, key: SessionKey) -> BoxFuture<'_, Option<CommittedRandomData>> {
+        Box::pin(async move { self.cache.get(&key).await.map(|session| session.data) })
+    }
+
+    fn reveal(&self, key: SessionKey) -> BoxFuture<'_, Option<CommittedRandomData>> {
+        Box::pin(async move {
+            let result = self
+                .cache
+                .entry(key)
+                .and_compute_with(|entry| async move {
+                    match entry.map(|entry| entry.into_value()) {
+                        Some(session) if session.revealed_at_ms.is_none() => {
+                            Op::Put(StoredSession {
+                                revealed_at_ms: Some(now_ms()),
+                                ..session
+                            })
+                        }
+                        _ => Op::Nop,
+                    }
+                })
+                .await;
+            match result {
+                CompResult::ReplacedWith(entry) => {
+                    let session = entry.into_value();
+                    self.audit.record(
+                        key.0.as_u128(),
+                        key.1,
+                        &session.data.requester,
+                        EvictionCause::Revealed,
+                        session.stored_at_ms,
+                    );
+                    Some(session.data)
+                }
+                CompResult::Unchanged(entry) => Some(entry.into_value().data),
+                _ => None,
+            }
+        })
+    }
+
+    fn contains(&self, key: SessionKey) -> BoxFuture<'_, bool> {
+        Box::pin(async move { self.cache.contains_key(&key) })
+    }
+
+    fn release(&self, key: SessionKey, requester: String) -> BoxFuture<'_, bool> {
+        Box::pin(async move {
+            let result = self
+                .cache
+                .entry(key)
+                .and_compute_with(|entry| async move {
+                    match entry {
+                        Some(entry)
+                            if entry.value().revealed_at_ms.is_none()
+                                && entry.value().data.requester == requester =>
+                        {
+                            Op::Remove
+                        }
+                        _ => Op::Nop,
+                    }
+                })
+                .await;
+            match result {
+                CompResult::Removed(entry) => {
+                    let session = entry.into_value();
+                    self.audit.record(
+                        key.0.as_u128(),
+                        key.1,
+                        &session.data.requester,
+                        EvictionCause::Released,
+                        session.stored_at_ms,
+                    );
+                    true
+                }
+                _ => false,
+            }
+        })
+    }
+
+    fn insert_proof(&self, proof: SessionProof) -> BoxFuture<'_, Result<(), CacheError>> {
+        Box::pin(async move {
+            let key = Uuid::from_u128(proof.commitment_id);
+            if !self.proofs.entry(key).or_insert(proof).await.is_fresh() {
+                return Err(CacheError::Taken);
+            }
+            Ok(())
+        })
+    }
+
+    fn get_proof(&self, commitment_id: Uuid) -> BoxFuture<'_, Option<SessionProof>> {
+        Box::pin(async move { self.proofs.get(&commitment_id).await })
+    }
+
+    fn update_proof(
+        &self,
+        commitment_id: Uuid,
+        update: ProofUpdate,
+    ) -> BoxFuture<'_, Option<SessionProof>> {
+        Box::pin(async move {
+            self.proofs
+                .entry(commitment_id)
+                .and_compute_with(|entry| async move {
+                    match entry {
+                        Some(entry) => {
+                            let mut proof = entry.into_value();
+                            update(&mut proof);
+                            Op::Put(proof)
+                        }
+                        None => Op::Nop,
+                    }
+                })
+                .await
+                .into_entry()
+                .map(|entry| entry.into_value())
+        })
+    }
+}
+
+// entry as persisted on disk, with the timestamps the expiration is checked against
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    commitment: Vec<u8>,
+    // opening sealed with the session key and the commitment as associated data, so that sealed
+    // openings can't be swapped between entries
+    opening: SealedOpening,
+    co_commitment: Option<CoCommitment>,
+    // unix time the entry was stored, in milliseconds
+    created_ms: u64,
+    // unix time the entry was last read, in milliseconds
+    accessed_ms: u64,
+    // reveal window of the session, in milliseconds
+    #[serde(default)]
+    deadline_ms: Option<u64>,
+    // party the opening was committed for
+    #[serde(default)]
+    requester: String,
+    // unix time the entry was first revealed, in milliseconds
+    #[serde(default)]
+    revealed_ms: Option<u64>,
+    // hash of the secret proven by the reveal requests of the session
+    #[serde(default)]
+    auth_hash: Option<Vec<u8>>,
+}
+
+impl StoredEntry {
+    fn new(
+        key: &[u8],
+        data: &CommittedRandomData,
+        keyring: &StoreKeyring,
+        now_ms: u64,
+    ) -> Result<Self, CacheError> {
+        let commitment = data.commitment.to_bytes();
+        let opening = Zeroizing::new(data.opening.to_bytes());
+        Ok(StoredEntry {
+            opening: keyring.seal(&sealing_aad(key, &commitment), &opening)?,
+            commitment,
+            co_commitment: data.co_commitment.clone(),
+            created_ms: now_ms,
+            accessed_ms: now_ms,
+            deadline_ms: data
+                .reveal_deadline
+                .map(|deadline| deadline.as_millis() as u64),
+            requester: data.requester.clone(),
+            revealed_ms: None,
+            auth_hash: data.auth_hash.clone(),
+        })
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, CacheError> {
+        serde_json::from_slice(bytes).map_err(|err| CacheError::Storage(err.to_string()))
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, CacheError> {
+        serde_json::to_vec(self).map_err(|err| CacheError::Storage(err.to_string()))
+    }
+
+    fn to_data(
+        &self,
+        key: &[u8],
+        keyring: &StoreKeyring,
+    ) -> Result<CommittedRandomData, CacheError> {
+        let corrupted =
+            |err: CommitmentError| CacheError::Storage(format!("corrupted entry: {}", err));
+        let opening = keyring.open(&sealing_aad(key, &self.commitment), &self.opening)?;
+        Ok(CommittedRandomData {
+            commitment: Commitment::from_slice(&self.commitment).map_err(corrupted)?,
+            opening: Opening::from_slice(&opening).map_err(corrupted)?,
+            co_commitment: self.co_commitment.clone(),
+            reveal_deadline: self.deadline_ms.map(Duration::from_millis),
+            requester: self.requester.clone(),
+            auth_hash: self.auth_hash.clone(),
+        })
+    }
+}
+
+// transcript as persisted on disk, with the time it was stored
+#[derive(Serialize, Deserialize)]
+struct StoredProof {
+    created_ms: u64,
+    proof: SessionProof,
+}
+
+impl StoredProof {
+    fn decode(bytes: &[u8]) -> Result<Self, CacheError> {
+        serde_json::from_slice(bytes).map_err(|err| CacheError::Storage(err.to_string()))
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, CacheError> {
+        serde_json::to_vec(self).map_err(|err| CacheError::Storage(err.to_string()))
+    }
+}
+
+/// On-disk session store backed by sled, entries survive restarts of the node.
+///
+/// Expiration is checked against wall-clock timestamps stored with every entry, so the time a
+/// node is down counts towards the TTL and TTI like with the in-memory store. Openings are sealed
+/// with the active key of the keyring and opened with the key they are tagged with.
+///
+/// A second tree indexes the entries by expiry time, so expired entries are purged, and the
+/// entries closest to their expiry evicted once the store is full, without scanning the store.
+/// Transcripts are kept in their own trees, indexed the same way, so draws dealt before a restart
+/// can still be finalized.
+///
+/// Sled reads and writes block, so every operation runs on the blocking threads of the runtime
+/// rather than on its workers.
+pub struct SledStore {
+    inner: Arc<SledInner>,
+}
+
+// trees and counters of the sled store, shared with the blocking tasks running its operations
+struct SledInner {
+    db: sled::Db,
+    // expiry time of every entry followed by its key, in milliseconds and big endian
+    expiry: sled::Tree,
+    limits: SessionLimits,
+    keyring: StoreKeyring,
+    audit: Arc<AuditLog>,
+    // number of entries, sled counts them by iterating the tree
+    len: AtomicU64,
+    // transcripts by session id, and their expiry index and count like the entries
+    proofs: sled::Tree,
+    proof_expiry: sled::Tree,
+    proof_len: AtomicU64,
+}
+
+impl SledStore {
+    pub fn open(
+        path: &Path,
+        limits: SessionLimits,
+        keyring: StoreKeyring,
+        audit: Arc<AuditLog>,
+    ) -> Result<Self, CacheError> {
+        Ok(SledStore {
+            inner: Arc::new(SledInner::open(path, limits, keyring, audit)?),
+        })
+    }
+
+    // runs a sled operation on the blocking threads of the runtime
+    async fn run<T, F>(&self, operation: F) -> Result<T, CacheError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&SledInner) -> Result<T, CacheError> + Send + 'static,
+    {
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || operation(&inner))
+            .await
+            .map_err(|err| CacheError::Storage(err.to_string()))?
+    }
+}
+
+impl SledInner {
+    fn open(
+        path: &Path,
+        limits: SessionLimits,
+        keyring: StoreKeyring,
+        audit: Arc<AuditLog>,
+    ) -> Result<Self, CacheError> {
+        let db = sled::open(path)?;
+        let expiry = db.open_tree("expiry")?;
+        let proofs = db.open_tree("proofs")?;
+        let proof_expiry = db.open_tree("proof-expiry")?;
+        let store = SledInner {
+            db,
+            expiry,
+            limits,
+            keyring,
+            audit,
+            len: AtomicU64::new(0),
+            proofs,
+            proof_expiry,
+            proof_len: AtomicU64::new(0),
+        };
+        store.rebuild_index()?;
+        store.rebuild_proof_index()?;
+        Ok(store)
+    }
+
+    // rebuilds the expiry index and the count of the transcripts, dropping corrupted ones
+    fn rebuild_proof_index(&self) -> Result<(), CacheError> {
+        self.proof_expiry.clear()?;
+        let mut len = 0;
+        for item in self.proofs.iter() {
+            let (key, value) = item?;
+            match StoredProof::decode(&value) {
+                Ok(stored) => {
+                    self.proof_expiry
+                        .insert(self.proof_index_key(&key, &stored), &[])?;
+                    len += 1;
+                }
+                Err(err) => {
+                    event!(
+                        Level::ERROR,
+                        "cache::store::corrupted proof {:?} {}",
+                        key,
+                        err
+                    );
+                    self.proofs.remove(&key)?;
+                }
+            }
+        }
+        self.proof_len.store(len, Ordering::Relaxed);
+        Ok(())
+    }
+
+    // transcripts expire a fixed time after they are stored, so their index key never changes
+    fn proof_index_key(&self, key: &[u8], stored: &StoredProof) -> Vec<u8> {
+        let expires_at_ms = stored
+            .created_ms
+            .saturating_add(self.limits.proof_retention.as_millis() as u64);
+        [&expires_at_ms.to_be_bytes()[..], key].concat()
+    }
+
+    // removes a transcript unless it was updated concurrently, returning whether it was removed
+    fn remove_proof(
+        &self,
+        key: &[u8],
+        current: sled::IVec,
+        stored: &StoredProof,
+    ) -> Result<bool, CacheError> {
+        if self
+            .proofs
+            .compare_and_swap(key, Some(current), None::<Vec<u8>>)?
+            .is_err()
+        {
+            return Ok(false);
+        }
+        self.proof_expiry
+            .remove(self.proof_index_key(key, stored))?;
+        self.proof_len.fetch_sub(1, Ordering::Relaxed);
+        Ok(true)
+    }
+
+    // reads a live transcript, dropping it if it expired or can't be decoded
+    fn read_proof(&self, key: &[u8]) -> Result<Option<(sled::IVec, StoredProof)>, CacheError> {
+        let Some(current) = self.proofs.get(key)? else {
+            return Ok(None);
+        };
+        match StoredProof::decode(&current) {
+            Ok(stored) if now_ms() >= self.proof_expires_at_ms(&stored) => {
+                self.remove_proof(key, current, &stored)?;
+                Ok(None)
+            }
+            Ok(stored) => Ok(Some((current, stored))),
+            Err(err) => {
+                event!(
+                    Level::ERROR,
+                    "cache::store::corrupted proof {:?} {}",
+                    key,
+                    err
+                );
+                if self
+                    .proofs
+                    .compare_and_swap(key, Some(current), None::<Vec<u8>>)?
+                    .is_ok()
+                {
+                    self.proof_len.fetch_sub(1, Ordering::Relaxed);
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    fn proof_expires_at_ms(&self, stored: &StoredProof) -> u64 {
+        stored
+            .created_ms
+            .saturating_add(self.limits.proof_retention.as_millis() as u64)
+    }
+
+    // purges the expired transcripts, then the transcripts closest to their expiry while the
+    // store is full
+    fn evict_proofs(&self, now_ms: u64) -> Result<(), CacheError> {
+        while let Some((index_key, _)) = self.proof_expiry.first()? {
+            let mut expires_at_ms = [0; 8];
+            expires_at_ms.copy_from_slice(&index_key[..8]);
+            if u64::from_be_bytes(expires_at_ms) > now_ms
+                && self.proof_len.load(Ordering::Relaxed) < self.limits.max_capacity
+            {
+                break;
+            }
+
+            let key = &index_key[8..];
+            match self.proofs.get(key)? {
+                Some(current) => match StoredProof::decode(&current) {
+                    Ok(stored) if index_key == self.proof_index_key(key, &stored) => {
+                        self.remove_proof(key, current, &stored)?;
+                    }
+                    // the index key of a transcript stored again since
+                    Ok(_) => {
+                        self.proof_expiry.remove(&index_key)?;
+                    }
+                    Err(_) => {
+                        self.proof_expiry.remove(&index_key)?;
+                        self.read_proof(key)?;
+                    }
+                },
+                // the index key of a transcript removed since
+                None => {
+                    self.proof_expiry.remove(&index_key)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn try_insert_proof(&self, proof: SessionProof) -> Result<(), CacheError> {
+        let now_ms = now_ms();
+        self.evict_proofs(now_ms)?;
+
+        let key = Uuid::from_u128(proof.commitment_id);
+        // an expired transcript is dropped first and can be replaced
+        self.read_proof(key.as_bytes())?;
+        let stored = StoredProof {
+            created_ms: now_ms,
+            proof,
+        };
+        self.proofs
+            .compare_and_swap(key.as_bytes(), None::<Vec<u8>>, Some(stored.encode()?))?
+            .map_err(|_| CacheError::Taken)?;
+        self.proof_expiry
+            .insert(self.proof_index_key(key.as_bytes(), &stored), &[])?;
+        self.proof_len.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn try_get_proof(&self, commitment_id: Uuid) -> Result<Option<SessionProof>, CacheError> {
+        Ok(self
+            .read_proof(commitment_id.as_bytes())?
+            .map(|(_, stored)| stored.proof))
+    }
+
+    fn try_update_proof(
+        &self,
+        commitment_id: Uuid,
+        update: &ProofUpdate,
+    ) -> Result<Option<SessionProof>, CacheError> {
+        let key = commitment_id.as_bytes();
+        loop {
+            let Some((current, mut stored)) = self.read_proof(key)? else {
+                return Ok(None);
+            };
+            update(&mut stored.proof);
+            // a concurrent update of the transcript is retried against it
+            if self
+                .proofs
+                .compare_and_swap(key, Some(current), Some(stored.encode()?))?
+                .is_ok()
+            {
+                return Ok(Some(stored.proof));
+            }
+        }
+    }
+
+    // rebuilds the expiry index and the entry count on startup, dropping corrupted entries
+    fn rebuild_index(&self) -> Result<(), CacheError> {
+        self.expiry.clear()?;
+        let mut len = 0;
+        for item in self.db.iter() {
+            let (key, value) = item?;
+            match StoredEntry::decode(&value) {
+                Ok(entry) => {
+                    self.index(&key, &entry)?;
+                    len += 1;
+                }
+                Err(_) => {
+                    self.db.remove(&key)?;
+                    self.record_corrupted(&key);
+                }
+            }
+        }
+        self.len.store(len, Ordering::Relaxed);
+        Ok(())
+    }
+
+    // unix time the entry expires at, in milliseconds
+    fn expires_at_ms(&self, entry: &StoredEntry) -> u64 {
+        if let Some(revealed_ms) = entry.revealed_ms {
+            return revealed_ms.saturating_add(self.limits.reveal_retention.as_millis() as u64);
+        }
+        match entry.deadline_ms {
+            Some(deadline_ms) => entry.created_ms.saturating_add(deadline_ms),
+            None => entry
+                .created_ms
+                .saturating_add(self.limits.time_to_live.as_millis() as u64)
+                .min(
+                    entry
+                        .accessed_ms
+                        .saturating_add(self.limits.time_to_idle.as_millis() as u64),
+                ),
+        }
+    }
+
+    fn is_expired(&self, entry: &StoredEntry, now_ms: u64) -> bool {
+        now_ms >= self.expires_at_ms(entry)
+    }
+
+    fn index_key(&self, key: &[u8], entry: &StoredEntry) -> Vec<u8> {
+        [&self.expires_at_ms(entry).to_be_bytes()[..], key].concat()
+    }
+
+    fn index(&self, key: &[u8], entry: &StoredEntry) -> Result<(), CacheError> {
+        self.expiry.insert(self.index_key(key, entry), &[])?;
+        Ok(())
+    }
+
+    // moves the entry in the index after a successful update, or out of it after a removal
+    fn reindex(
+        &self,
+        key: &[u8],
+        old_index_key: Vec<u8>,
+        new: Option<&StoredEntry>,
+    ) -> Result<(), CacheError> {
+        self.expiry.remove(old_index_key)?;
+        match new {
+            Some(new) => self.index(key, new),
+            None => {
+                self.len.fetch_sub(1, Ordering::Relaxed);
+                Ok(())
+            }
+        }
+    }
+
+    // removes an entry unless it was updated concurrently, returning whether it was removed
+    fn remove(
+        &self,
+        key: &[u8],
+        current: sled::IVec,
+        entry: &StoredEntry,
+    ) -> Result<bool, CacheError> {
+        if self
+            .db
+            .compare_and_swap(key, Some(current), None::<Vec<u8>>)?
+            .is_err()
+        {
+            return Ok(false);
+        }
+        self.reindex(key, self.index_key(key, entry), None)?;
+        Ok(true)
+    }
+
+    fn record(&self, key: &[u8], entry: &StoredEntry, cause: EvictionCause) {
+        // revealed entries are audited on their first reveal
+        if entry.revealed_ms.is_some() && cause != EvictionCause::Revealed {
+            return;
+        }
+        if let Some((commitment_id, role)) = decode_key(key) {
+            self.audit.record(
+                commitment_id.as_u128(),
+                role,
+                &entry.requester,
+                cause,
+                entry.created_ms,
+            );
+        }
+    }
+
+    fn record_expired(&self, key: &[u8], entry: &StoredEntry, now_ms: u64) {
+        let age = Duration::from_millis(now_ms.saturating_sub(entry.created_ms));
+        let reveal_deadline = entry.deadline_ms.map(Duration::from_millis);
+        self.record(
+            key,
+            entry,
+            self.limits.expiration_cause(reveal_deadline, age),
+        );
+    }
+
+    // corrupted entries have no requester nor storage time to report
+    fn record_corrupted(&self, key: &[u8]) {
+        event!(Level::ERROR, "cache::store::corrupted entry {:?}", key);
+        if let Some((commitment_id, role)) = decode_key(key) {
+            self.audit.record(
+                commitment_id.as_u128(),
+                role,
+                "unknown",
+                EvictionCause::Corrupted,
+                0,
+            );
+        }
+    }
+
+    // decodes an entry, dropping it if it can't be decoded
+    fn decode(&self, key: &[u8], current: &sled::IVec) -> Result<Option<StoredEntry>, CacheError> {
+        match StoredEntry::decode(current) {
+            Ok(entry) => Ok(Some(entry)),
+            Err(_) => {
+                self.drop_corrupted(key, current.clone())?;
+                Ok(None)
+            }
+        }
+    }
+
+    // drops an entry that can't be decoded, unless it was replaced concurrently
+    fn drop_corrupted(&self, key: &[u8], current: sled::IVec) -> Result<(), CacheError> {
+        if self
+            .db
+            .compare_and_swap(key, Some(current), None::<Vec<u8>>)?
+            .is_ok()
+        {
+            self.len.fetch_sub(1, Ordering::Relaxed);
+            self.record_corrupted(key);
+        }
+        Ok(())
+    }
+
+    // purges the expired entries from the front of the expiry index, then the entries closest to
+    // their expiry while the store is full to make room for a new entry
+    fn evict(&self, now_ms: u64) -> Result<(), CacheError> {
+        while let Some((index_key, _)) = self.expiry.first()? {
+            let mut expires_at_ms = [0; 8];
+            expires_at_ms.copy_from_slice(&index_key[..8]);
+            let expired = u64::from_be_bytes(expires_at_ms) <= now_ms;
+            if !expired && self.len.load(Ordering::Relaxed) < self.limits.max_capacity {
+                break;
+            }
+
+            let key = &index_key[8..];
+            let Some(current) = self.db.get(key)? else {
+                // the index key of an entry removed since
+                self.expiry.remove(&index_key)?;
+                continue;
+            };
+            match StoredEntry::decode(&current) {
+                // the index key of an entry updated since
+                Ok(entry) if index_key != self.index_key(key, &entry) => {
+                    self.expiry.remove(&index_key)?;
+                }
+                Ok(entry) => {
+                    if self.remove(key, current, &entry)? {
+                        match expired {
+                            true => self.record_expired(key, &entry, now_ms),
+                            false => self.record(key, &entry, EvictionCause::Capacity),
+                        }
+                    }
+                }
+                Err(_) => {
+                    self.expiry.remove(&index_key)?;
+                    self.drop_corrupted(key, current)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn try_insert(&self, key: SessionKey, data: &CommittedRandomData) -> Result<(), CacheError> {
+        // a taken key doesn't make room for itself
+        if self.try_contains(key)? {
+            return Err(CacheError::Taken);
+        }
+        let now_ms = now_ms();
+        self.evict(now_ms)?;
+
+        let key = encode_key(key);
+        let current = self.db.get(key)?;
+        let replaced = current.as_deref().map(StoredEntry::decode);
+        if let Some(Ok(entry)) = &replaced {
+            if !self.is_expired(entry, now_ms) {
+                return Err(CacheError::Taken);
+            }
+        }
+
+        let entry = StoredEntry::new(&key, data, &self.keyring, now_ms)?;
+        self.db
+            .compare_and_swap(key, current, Some(entry.encode()?))?
+            .map_err(|_| CacheError::Taken)?;
+        match replaced {
+            Some(Ok(expired)) => {
+                self.reindex(&key, self.index_key(&key, &expired), Some(&entry))?;
+                self.record_expired(&key, &expired, now_ms);
+            }
+            Some(Err(_)) => {
+                self.index(&key, &entry)?;
+                self.record_corrupted(&key);
+            }
+            None => {
+                self.index(&key, &entry)?;
+                self.len.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+
+    fn try_get(&self, key: SessionKey) -> Result<Option<CommittedRandomData>, CacheError> {
+        let now_ms = now_ms();
+        let key = encode_key(key);
+        let Some(current) = self.db.get(key)? else {
+            return Ok(None);
+        };
+
+        let Some(mut entry) = self.decode(&key, &current)? else {
+            return Ok(None);
+        };
+        if self.is_expired(&entry, now_ms) {
+            if self.remove(&key, current, &entry)? {
+                self.record_expired(&key, &entry, now_ms);
+            }
+            return Ok(None);
+        }
+
+        // a concurrent removal wins over the access time update
+        let index_key = self.index_key(&key, &entry);
+        entry.accessed_ms = now_ms;
+        if self
+            .db
+            .compare_and_swap(key, Some(current), Some(entry.encode()?))?
+            .is_ok()
+        {
+            self.reindex(&key, index_key, Some(&entry))?;
+        }
+        entry.to_data(&key, &self.keyring).map(Some)
+    }
+
+    fn try_reveal(&self, key: SessionKey) -> Result<Option<CommittedRandomData>, CacheError> {
+        let now_ms = now_ms();
+        let key = encode_key(key);
+        loop {
+            let Some(current) = self.db.get(key)? else {
+                return Ok(None);
+            };
+
+            let Some(mut entry) = self.decode(&key, &current)? else {
+                return Ok(None);
+            };
+            if self.is_expired(&entry, now_ms) {
+                if self.remove(&key, current, &entry)? {
+                    self.record_expired(&key, &entry, now_ms);
+                }
+                return Ok(None);
+            }
+            if entry.revealed_ms.is_some() {
+                return entry.to_data(&key, &self.keyring).map(Some);
+            }
+
+            // a concurrent update of the entry, e.g. another reveal, is retried against it
+            let index_key = self.index_key(&key, &entry);
+            entry.revealed_ms = Some(now_ms);
+            if self
+                .db
+                .compare_and_swap(key, Some(current), Some(entry.encode()?))?
+                .is_ok()
+            {
+                self.reindex(&key, index_key, Some(&entry))?;
+                self.record(&key, &entry, EvictionCause::Revealed);
+                return entry.to_data(&key, &self.keyring).map(Some);
+            }
+        }
+    }
+
+    fn try_release(&self, key: SessionKey, requester: &str) -> Result<bool, CacheError> {
+        let now_ms = now_ms();
+        let key = encode_key(key);
+        loop {
+            let Some(current) = self.db.get(key)? else {
+                return Ok(false);
+            };
+
+            let Some(entry) = self.decode(&key, &current)? else {
+                return Ok(false);
+            };
+            if self.is_expired(&entry, now_ms) {
+                if self.remove(&key, current, &entry)? {
+                    self.record_expired(&key, &entry, now_ms);
+                }
+                return Ok(false);
+            }
+            if entry.revealed_ms.is_some() || entry.requester != requester {
+                return Ok(false);
+            }
+
+            // a concurrent update of the entry, e.g. a reveal, is checked again
+            if self.remove(&key, current, &entry)? {
+                self.record(&key, &entry, EvictionCause::Released);
+                return Ok(true);
+            }
+        }
+    }
+
+    fn try_contains(&self, key: SessionKey) -> Result<bool, CacheError> {
+        let key = encode_key(key);
+        match self.db.get(key)? {
+            Some(current) => Ok(self
+                .decode(&key, &current)?
+                .is_some_and(|entry| !self.is_expired(&entry, now_ms()))),
+            None => Ok(false),
+        }
+    }
+}
+
+impl SessionStore for SledStore {
+    fn insert(
+        &self,
+        key: SessionKey,
+        data: CommittedRandomData,
+    ) -> BoxFuture<'_, Result<(), CacheError>> {
+        Box::pin(async move {
+            self.run(move |inner| inner.try_insert(key, &data)).await?;
+            // the opening is only handed out once it is durable
+            self.inner.db.flush_async().await?;
+            Ok(())
+        })
+    }
+
+    fn get(&self, key: SessionKey) -> BoxFuture<'_, Option<CommittedRandomData>> {
+        Box::pin(async move {
+            let result = self.run(move |inner| inner.try_get(key)).await;
+            log_error("get", key, result).flatten()
+        })
+    }
+
+    fn reveal(&self, key: SessionKey) -> BoxFuture<'_, Option<CommittedRandomData>> {
+        Box::pin(async move {
+            let result = self.run(move |inner| inner.try_reveal(key)).await;
+            log_error("reveal", key, result).flatten()
+        })
+    }
+
+    fn contains(&self, key: SessionKey) -> BoxFuture<'_, bool> {
+        Box::pin(async move {
+            let result = self.run(move |inner| inner.try_contains(key)).await;
+            log_error("contains", key, result).unwrap_or(false)
+        })
+    }
+
+    fn release(&self, key: SessionKey, requester: String) -> BoxFuture<'_, bool> {
+        Box::pin(async move {
+            let result = self
+                .run(move |inner| inner.try_release(key, &requester))
+                .await;
+            log_error("release", key, result).unwrap_or(false)
+        })
+    }
+
+    fn insert_proof(&self, proof: SessionProof) -> BoxFuture<'_, Result<(), CacheError>> {
+        Box::pin(async move {
+            self.run(move |inner| inner.try_insert_proof(proof)).await?;
+            self.inner.db.flush_async().await?;
+            Ok(())
+        })
+    }
+
+    fn get_proof(&self, commitment_id: Uuid) -> BoxFuture<'_, Option<SessionProof>> {
+        Box::pin(async move {
+            let key = (commitment_id, Role::Dealer);
+            let result = self
+                .run(move |inner| inner.try_get_proof(commitment_id))
+                .await;
+            log_error("get_proof", key, result).flatten()
+        })
+    }
+
+    fn update_proof(
+        &self,
+        commitment_id: Uuid,
+        update: ProofUpdate,
+    ) -> BoxFuture<'_, Option<SessionProof>> {
+        Box::pin(async move {
+            let key = (commitment_id, Role::Dealer);
+            let result = self
+                .run(move |inner| inner.try_update_proof(commitment_id, &update))
+                .await;
+            // the openings recorded in the transcript are only reported once they are durable
+            let result = match result {
+                Ok(proof) => self
+                    .inner
+                    .db
+                    .flush_async()
+                    .await
+                    .map(|_| proof)
+                    .map_err(Into::into),
+                Err(err) => Err(err),
+            };
+            log_error("update_proof", key, result).flatten()
+        })
+    }
+}
+
+// storage failures on reads are reported as missing entries
+fn log_error<T>(operation: &str, key: SessionKey, result: Result<T, CacheError>) -> Option<T> {
+    result
+        .map_err(|err| {
+            event!(
+                Level::ERROR,
+                "cache::store::{} {} {} {}",
+                operation,
+                key.0,
+                key.1,
+                err
+            )
+        })
+        .ok()
+}
+
+// sealed openings are bound to the entry key and the commitment
+fn sealing_aad(key: &[u8], commitment: &[u8]) -> Vec<u8> {
+    [key, commitment].concat()
+}
+
+// session id followed by the role
+fn encode_key((commitment_id, role): SessionKey) -> [u8; 17] {
+    let mut key = [0; 17];
+    key[..16].copy_from_slice(commitment_id.as_bytes());
+    key[16] = match role {
+        Role::Dealer => 0,
+        Role::CoCommitter => 1,
+    };
+    key
+}
+
+fn decode_key(key: &[u8]) -> Option<SessionKey> {
+    let commitment_id = Uuid::from_slice(key.get(..16)?).ok()?;
+    let role = match key.get(16)? {
+        0 => Role::Dealer,
+        1 => Role::CoCommitter,
+        _ => return None,
+    };
+    Some((commitment_id, role))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use super::*;
+    use crate::cache::audit::AuditQuery;
+
+    fn create_test_data(random: u64) -> CommittedRandomData {
+        let (commitment, opening) = Commitment::new(random);
+        CommittedRandomData {
+            commitment,
+            opening,
+            co_commitment: None,
+            reveal_deadline: None,
+            requester: "node:1".to_string(),
+            auth_hash: None,
+        }
+    }
+
+    fn create_test_keyring(keys: &[&str]) -> StoreKeyring {
+        StoreKeyring::from_entries(keys.iter().copied())
+            .unwrap()
+            .unwrap()
+    }
+
+    fn create_test_audit() -> Arc<AuditLog> {
+        Arc::new(AuditLog::open(None).unwrap())
+    }
+
+    fn audited_causes(audit: &AuditLog) -> Vec<EvictionCause> {
+        audit
+            .records(&AuditQuery::default())
+            .into_iter()
+            .map(|record| record.cause)
+            .collect()
+    }
+
+    // reopens a dropped store, sled only releases the lock of its directory once the flusher
+    // thread of the dropped store has stopped
+    fn reopen_test_store(
+        path: &std::path::Path,
+        limits: SessionLimits,
+        keys: &[&str],
+        audit: Arc<AuditLog>,
+    ) -> SledStore {
+        for _ in 0..100 {
+            match SledStore::open(path, limits, create_test_keyring(keys), audit.clone()) {
+                Ok(store) => return store,
+                Err(_) => std::thread::sleep(Duration::from_millis(20)),
+            }
+        }
+        panic!("the store lock wasn't released");
+    }
+
+    fn create_test_path(name: &str) -> std::path::PathBuf {
+        temp_dir().join(format!("random-pedersen-test-{}-{}", name, Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_sled_store_survives_restart() {
+        let path = create_test_path("store");
+        let old = StoreKeyring::generate("old").unwrap();
+        let new = StoreKeyring::generate("new").unwrap();
+        let key = (Uuid::new_v4(), Role::Dealer);
+        let data = create_test_data(123124);
+
+        let store = SledStore::open(
+            &path,
+            NodeConfig::default().session_limits(),
+            create_test_keyring(&[&old]),
+            create_test_audit(),
+        )
+        .unwrap();
+        store.insert(key, data.clone()).await.unwrap();
+        assert_eq!(
+            store.insert(key, create_test_data(1)).await,
+            Err(CacheError::Taken)
+        );
+
+        // the opening is sealed on disk
+        let stored = store.inner.db.get(encode_key(key)).unwrap().unwrap();
+        let entry = StoredEntry::decode(&stored).unwrap();
+        assert_eq!(entry.opening.key_id, "old");
+        let opening = data.opening.to_bytes();
+        assert_ne!(entry.opening.ciphertext[..opening.len()], opening[..]);
+        drop(store);
+
+        // entries sealed before a key rotation are still opened
+        let audit = create_test_audit();
+        let store = reopen_test_store(
+            &path,
+            NodeConfig::default().session_limits(),
+            &[&new, &old],
+            audit.clone(),
+        );
+        assert!(store.contains(key).await);
+        assert!(!store.contains((key.0, Role::CoCommitter)).await);
+        let stored = store.reveal(key).await.unwrap();
+        assert_eq!(stored.commitment, data.commitment);
+        assert_eq!(stored.opening, data.opening);
+        assert_eq!(audited_causes(&audit), vec![EvictionCause::Revealed]);
+        let record = &audit.records(&AuditQuery::default())[0];
+        assert_eq!(
+            (record.commitment_id, record.role),
+            (key.0.as_u128(), key.1)
+        );
+        assert_eq!(record.requester, "node:1");
+
+        drop(store);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sled_store_expiration() {
+        let path = create_test_path("store-expiration");
+        let limits = SessionLimits {
+            time_to_live: Duration::from_millis(200),
+            ..NodeConfig::default().session_limits()
+        };
+        let key_entry = StoreKeyring::generate("test").unwrap();
+        let audit = create_test_audit();
+        let store = SledStore::open(
+            &path,
+            limits,
+            create_test_keyring(&[&key_entry]),
+            audit.clone(),
+        )
+        .unwrap();
+
+        // expired entries are gone and their key can be taken again
+        let key = (Uuid::new_v4(), Role::Dealer);
+        store.insert(key, create_test_data(1)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(!store.contains(key).await);
+        assert!(store.get(key).await.is_none());
+        assert_eq!(audited_causes(&audit), vec![EvictionCause::Expired]);
+        store.insert(key, create_test_data(2)).await.unwrap();
+        assert!(store.contains(key).await);
+
+        // entries expired unread while the node was down are swept and audited after a restart
+        drop(store);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let audit = create_test_audit();
+        let store = reopen_test_store(&path, limits, &[&key_entry], audit.clone());
+        store
+            .insert((Uuid::new_v4(), Role::Dealer), create_test_data(3))
+            .await
+            .unwrap();
+        assert_eq!(audited_causes(&audit), vec![EvictionCause::Expired]);
+
+        drop(store);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sled_store_capacity() {
+        let path = create_test_path("store-capacity");
+        let limits = SessionLimits {
+            max_capacity: 2,
+            ..NodeConfig::default().session_limits()
+        };
+        let key_entry = StoreKeyring::generate("test").unwrap();
+        let audit = create_test_audit();
+        let store = SledStore::open(
+            &path,
+            limits,
+            create_test_keyring(&[&key_entry]),
+            audit.clone(),
+        )
+        .unwrap();
+
+        // the entry closest to its expiry, here the least recently read one, is evicted once the
+        // store is full
+        let first = (Uuid::new_v4(), Role::Dealer);
+        let second = (Uuid::new_v4(), Role::Dealer);
+        let third = (Uuid::new_v4(), Role::CoCommitter);
+        store.insert(first, create_test_data(1)).await.unwrap();
+        store.insert(second, create_test_data(2)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(store.get(first).await.is_some());
+        store.insert(third, create_test_data(3)).await.unwrap();
+        assert!(store.contains(first).await);
+        assert!(!store.contains(second).await);
+        assert!(store.contains(third).await);
+        let records = audit.records(&AuditQuery::default());
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].commitment_id, second.0.as_u128());
+        assert_eq!(records[0].cause, EvictionCause::Capacity);
+
+        drop(store);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sled_store_corrupted_entry() {
+        let path = create_test_path("store-corrupted");
+        let key_entry = StoreKeyring::generate("test").unwrap();
+        let open = |audit: Arc<AuditLog>| {
+            reopen_test_store(
+                &path,
+                NodeConfig::default().session_limits(),
+                &[&key_entry],
+                audit,
+            )
+        };
+
+        // corrupted entries are dropped and audited when read
+        let audit = create_test_audit();
+        let store = open(audit.clone());
+        let read = (Uuid::new_v4(), Role::Dealer);
+        let reopened = (Uuid::new_v4(), Role::CoCommitter);
+        store.insert(read, create_test_data(1)).await.unwrap();
+        store.insert(reopened, create_test_data(2)).await.unwrap();
+        store
+            .inner
+            .db
+            .insert(encode_key(read), b"corrupted")
+            .unwrap();
+        store
+            .inner
+            .db
+            .insert(encode_key(reopened), b"corrupted")
+            .unwrap();
+        assert!(store.get(read).await.is_none());
+        assert!(!store.inner.db.contains_key(encode_key(read)).unwrap());
+        let records = audit.records(&AuditQuery::default());
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].commitment_id, read.0.as_u128());
+        assert_eq!(records[0].cause, EvictionCause::Corrupted);
+        drop(store);
+
+        // and when the store is reopened
+        let audit = create_test_audit();
+        let store = open(audit.clone());
+        assert!(!store.inner.db.contains_key(encode_key(reopened)).unwrap());
+        assert_eq!(audited_causes(&audit), vec![EvictionCause::Corrupted]);
+        assert_eq!(store.inner.len.load(Ordering::Relaxed), 0);
+
+        drop(store);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    fn create_test_proof(commitment_id: u128) -> SessionProof {
+        SessionProof {
+            commitment_id,
+            dealer_id: 1,
+            node_ids: vec![1],
+            commitment: Vec::new(),
+            commitments: Vec::new(),
+            openings: Vec::new(),
+            random: None,
+            output: None,
+            random_mode: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sled_store_proofs() {
+        let path = create_test_path("store-proofs");
+        let key_entry = StoreKeyring::generate("test").unwrap();
+        let limits = SessionLimits {
+            max_capacity: 2,
+            proof_retention: Duration::from_millis(300),
+            ..NodeConfig::default().session_limits()
+        };
+        let store = reopen_test_store(&path, limits, &[&key_entry], create_test_audit());
+
+        // transcripts are never overwritten, and updated in place
+        let first = Uuid::new_v4();
+        store
+            .insert_proof(create_test_proof(first.as_u128()))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.insert_proof(create_test_proof(first.as_u128())).await,
+            Err(CacheError::Taken)
+        );
+        let update: ProofUpdate = Arc::new(|proof: &mut SessionProof| proof.output = Some(vec![1]));
+        let updated = store.update_proof(first, update.clone()).await.unwrap();
+        assert_eq!(updated.output, Some(vec![1]));
+        assert!(store.update_proof(Uuid::new_v4(), update).await.is_none());
+
+        // and survive a restart
+        drop(store);
+        let store = reopen_test_store(&path, limits, &[&key_entry], create_test_audit());
+        let stored = store.get_proof(first).await.unwrap();
+        assert_eq!(stored.output, Some(vec![1]));
+        assert_eq!(store.inner.proof_len.load(Ordering::Relaxed), 1);
+
+        // the transcript closest to its expiry is evicted once the store is full
+        let second = Uuid::new_v4();
+        let third = Uuid::new_v4();
+        store
+            .insert_proof(create_test_proof(second.as_u128()))
+            .await
+            .unwrap();
+        store
+            .insert_proof(create_test_proof(third.as_u128()))
+            .await
+            .unwrap();
+        assert!(store.get_proof(first).await.is_none());
+        assert!(store.get_proof(second).await.is_some());
+
+        // and transcripts expire after the proof retention
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert!(store.get_proof(third).await.is_none());
+        assert_eq!(store.inner.proof_len.load(Ordering::Relaxed), 1);
+
+        drop(store);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reveal_deadline() {
+        let path = create_test_path("store-deadline");
+        let limits = SessionLimits {
+            time_to_live: Duration::from_millis(200),
+            time_to_idle: Duration::from_millis(200),
+            ..NodeConfig::default().session_limits()
+        };
+        let key_entry = StoreKeyring::generate("test").unwrap();
+        let stores: [Arc<dyn SessionStore>; 2] = [
+            Arc::new(MemoryStore::new(limits, create_test_audit())),
+            Arc::new(
+                SledStore::open(
+                    &path,
+                    limits,
+                    create_test_keyring(&[&key_entry]),
+                    create_test_audit(),
+                )
+                .unwrap(),
+            ),
+        ];
+
+        for store in stores {
+            // the reveal deadline of a session overrides the time to live and idle
+            let short = (Uuid::new_v4(), Role::Dealer);
+            let long = (Uuid::new_v4(), Role::Dealer);
+            let expiring = (Uuid::new_v4(), Role::CoCommitter);
+            store.insert(short, create_test_data(1)).await.unwrap();
+            let mut data = create_test_data(2);
+            data.reveal_deadline = Some(Duration::from_secs(60));
+            store.insert(long, data).await.unwrap();
+            let mut data = create_test_data(3);
+            data.reveal_deadline = Some(Duration::from_millis(100));
+            store.insert(expiring, data).await.unwrap();
+
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            assert!(store.get(short).await.is_none());
+            assert!(store.get(expiring).await.is_none());
+            let stored = store.get(long).await.unwrap();
+            assert_eq!(stored.reveal_deadline, Some(Duration::from_secs(60)));
+        }
+
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_audit() {
+        let limits = SessionLimits {
+            time_to_idle: Duration::from_millis(100),
+            ..NodeConfig::default().session_limits()
+        };
+        let audit = create_test_audit();
+        let store = MemoryStore::new(limits, audit.clone());
+
+        // revealed entries are audited on removal, unread ones once the cache purges them
+        let revealed = (Uuid::new_v4(), Role::Dealer);
+        let idle = (Uuid::new_v4(), Role::CoCommitter);
+        let expired = (Uuid::new_v4(), Role::Dealer);
+        store.insert(revealed, create_test_data(1)).await.unwrap();
+        store.insert(idle, create_test_data(2)).await.unwrap();
+        let mut data = create_test_data(3);
+        data.reveal_deadline = Some(Duration::from_millis(100));
+        store.insert(expired, data).await.unwrap();
+        assert!(store.reveal(revealed).await.is_some());
+
+        // the cache purges expired entries at a granularity of about a second
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            store.run_pending_tasks().await;
+            if audited_causes(&audit).len() == 3 {
+                break;
+            }
+        }
+        let mut causes = audited_causes(&audit);
+        causes.sort_by_key(|cause| format!("{:?}", cause));
+        assert_eq!(
+            causes,
+            vec![
+                EvictionCause::Expired,
+                EvictionCause::Idle,
+                EvictionCause::Revealed
+            ]
+        );
+        let stats = audit.requester_stats();
+        assert_eq!(stats["node:1"].sessions, 3);
+        assert_eq!(stats["node:1"].withheld_rate, 2.0 / 3.0);
+    }
+
+    #[tokio::test]
+    async fn test_reveal_retention() {
+        let path = create_test_path("store-retention");
+        let limits = SessionLimits {
+            reveal_retention: Duration::from_millis(200),
+            ..NodeConfig::default().session_limits()
+        };
+        let key_entry = StoreKeyring::generate("test").unwrap();
+        let audit = create_test_audit();
+        let stores: [Arc<dyn SessionStore>; 2] = [
+            Arc::new(MemoryStore::new(limits, audit.clone())),
+            Arc::new(
+                SledStore::open(
+                    &path,
+                    limits,
+                    create_test_keyring(&[&key_entry]),
+                    audit.clone(),
+                )
+                .unwrap(),
+            ),
+        ];
+
+        for store in stores {
+            // repeated reveals return the same opening until the retention elapses
+            let key = (Uuid::new_v4(), Role::Dealer);
+            let data = create_test_data(1);
+            store.insert(key, data.clone()).await.unwrap();
+            let revealed = store.reveal(key).await.unwrap();
+            assert_eq!(revealed.opening, data.opening);
+            let repeated = store.reveal(key).await.unwrap();
+            assert_eq!(repeated.opening, data.opening);
+
+            // a revealed session keeps its key until it is purged
+            assert!(store.contains(key).await);
+            assert_eq!(
+                store.insert(key, create_test_data(2)).await,
+                Err(CacheError::Taken)
+            );
+
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            assert!(store.reveal(key).await.is_none());
+            assert!(!store.contains(key).await);
+        }
+
+        // the reveal is audited once per session
+        assert_eq!(
+            audited_causes(&audit),
+            vec![EvictionCause::Revealed, EvictionCause::Revealed]
+        );
+
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_release() {
+        let path = create_test_path("store-release");
+        let limits = NodeConfig::default().session_limits();
+        let key_entry = StoreKeyring::generate("test").unwrap();
+        let audit = create_test_audit();
+        let stores: [Arc<dyn SessionStore>; 2] = [
+            Arc::new(MemoryStore::new(limits, audit.clone())),
+            Arc::new(
+                SledStore::open(
+                    &path,
+                    limits,
+                    create_test_keyring(&[&key_entry]),
+                    audit.clone(),
+                )
+                .unwrap(),
+            ),
+        ];
+
+        for store in stores {
+            // only the requester of an entry releases it
+            let key = (Uuid::new_v4(), Role::CoCommitter);
+            store.insert(key, create_test_data(1)).await.unwrap();
+            assert!(!store.release(key, "node:2".to_string()).await);
+            assert!(store.release(key, "node:1".to_string()).await);
+            assert!(!store.contains(key).await);
+            assert!(!store.release(key, "node:1".to_string()).await);
+
+            // a revealed entry was used and is kept
+            let key = (Uuid::new_v4(), Role::CoCommitter);
+            store.insert(key, create_test_data(2)).await.unwrap();
+            assert!(store.reveal(key).await.is_some());
+            assert!(!store.release(key, "node:1".to_string()).await);
+            assert!(store.contains(key).await);
+        }
+
+        // released entries aren't withheld by their requester
+        assert_eq!(
+            audited_causes(&audit),
+            vec![
+                EvictionCause::Released,
+                EvictionCause::Revealed,
+                EvictionCause::Released,
+                EvictionCause::Revealed
+            ]
+        );
+        assert_eq!(audit.requester_stats()["node:1"].withheld_rate, 0.0);
+
+        std::fs::remove_dir_all(path).unwrap();
+    }
+}
@@ -2,11 +2,127 @@ mod cache;
 mod routes;
 mod utils;
 
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::Router;
+use rustls::ServerConfig;
+use tokio::net::TcpListener;
 use tracing::{event, Level};
 
+use utils::{
+    config::NodeConfig,
+    errors::ConfigError,
+    tls::{create_server_config, serve_tls},
+};
+
 use cache::state::create_state;
-use routes::create_routes;
-use utils::config::get_port;
+use routes::{create_peer_routes, create_public_routes};
+
+/// Routers of a node, the client routes and the peer routes, to be served by an existing axum server.
+///
+/// Unless the peers talk mutual TLS, the peer routes have to be served on an internal listener only.
+/// Fails if the audit log, the session store, the peer client or the signing key can't be set up.
+pub fn create_routes(config: NodeConfig) -> Result<(Router, Router), ConfigError> {
+    let state = Arc::new(create_state(config)?);
+    Ok((
+        create_public_routes(state.clone()),
+        create_peer_routes(state),
+    ))
+}
+
+/// Pedersen commitments, their openings and the proofs attached to them.
+pub mod commitment {
+    pub use crate::utils::{
+        commitment::{Commitment, Opening, OpeningValue, RANGE_PROOF_BITS},
+        errors::{CommitmentError, CommitmentGenerationError},
+    };
+}
+
+/// Types exchanged between the nodes and returned to clients.
+pub mod types {
+    pub use crate::cache::state::{
+        CommitRandomRequest, CommitmentForRandom, CommitmentForRandoms, DerivedRandoms,
+        DerivedStream, ProofRequest, RevealRequest, RevealResponse, RevealedRandom, Role,
+        SessionProof,
+    };
+}
+
+/// Session stores holding the unrevealed openings of a node.
+pub mod store {
+    pub use crate::{
+        cache::{
+            state::CommittedRandomData,
+            store::{
+                create_session_store, MemoryStore, ProofUpdate, SessionKey, SessionLimits,
+                SessionStore, SledStore,
+            },
+        },
+        utils::{
+            errors::CacheError,
+            sealing::{SealedOpening, StoreKeyring, STORE_KEY_LEN},
+        },
+    };
+}
+
+/// Audit trail of the openings leaving the session store, revealed or withheld.
+pub mod audit {
+    pub use crate::cache::audit::{
+        AuditLog, AuditQuery, AuditRecord, EvictionCause, RequesterStats,
+    };
+}
+
+/// Derivation of values from the output of a session, reproducible by any verifier.
+pub mod derivation {
+    pub use crate::utils::{
+        derivation::{
+            derive_range, derive_rng, derive_sample, derive_shuffle, derive_stream, derive_weighted,
+        },
+        errors::DerivationError,
+    };
+}
+
+/// Node configuration, loaded from a TOML file, environment variables and command-line flags.
+pub mod config {
+    pub use crate::utils::{
+        config::{NodeConfig, PeerConfig, RandomMode, StoreBackend},
+        errors::ConfigError,
+    };
+}
+
+/// Offline verification of session transcripts and extraction of their output.
+pub mod transcript {
+    pub use crate::utils::{
+        errors::TranscriptError,
+        extraction::{extract_random, OUTPUT_LENGTH},
+        transcript::{verify_transcript, verify_transcript_signatures},
+    };
+}
+
+/// Ed25519 node identities and the messages signed by the nodes.
+pub mod identity {
+    pub use crate::utils::identity::{
+        auth_hash, commitment_message, release_message, reveal_message, signed_message,
+        verify_auth_proof, verify_signature, NodeIdentity, AUTH_HASH_LEN,
+    };
+}
+
+/// Mutual TLS between the nodes, with a local CA issuing the node certificates.
+pub mod tls {
+    pub use crate::utils::tls::{
+        generate_ca, generate_node_certificate, get_certificate_name, PeerCertificate,
+    };
+}
+
+/// HTTP client for the node endpoints.
+pub mod client {
+    pub use crate::utils::peers::{
+        get_audit_endpoint, get_audit_requesters_endpoint, get_commit_to_random_endpoint,
+        get_nodes_endpoint, get_reveal_random_endpoint, get_session_proof_path,
+        get_session_range_path, get_session_sample_path, get_session_shuffle_path,
+        get_session_stream_path, get_session_weighted_path, send_commitment_request,
+        send_finalize_proof_request, send_proof_request, send_reveal_request,
+    };
+}
 
 pub async fn run() {
     event!(Level::DEBUG, "lib::run");
@@ -15,17 +131,99 @@ pub async fn run() {
         .with_max_level(tracing::Level::DEBUG)
         .init();
 
-    let app = create_routes(create_state());
-    let address = format!("0.0.0.0:{}", get_port());
+    let config = match NodeConfig::load() {
+        Ok(config) => config,
+        Err(err) => {
+            event!(Level::ERROR, "lib::run::config {}", err);
+            std::process::exit(1);
+        }
+    };
+    let address = SocketAddr::from(([0, 0, 0, 0], config.port));
 
     event!(
         Level::DEBUG,
         "lib::run {}",
-        utils::peers::get_node_address()
+        utils::peers::get_node_address(&config)
     );
 
-    axum::Server::bind(&address.parse().unwrap())
-        .serve(app.into_make_service())
-        .await
-        .unwrap();
+    let server_config = match create_server_config(&config) {
+        Ok(server_config) => server_config,
+        Err(err) => {
+            event!(Level::ERROR, "lib::run::tls {}", err);
+            std::process::exit(1);
+        }
+    };
+    let state = match create_state(config) {
+        Ok(state) => state,
+        Err(err) => {
+            event!(Level::ERROR, "lib::run::state {}", err);
+            std::process::exit(1);
+        }
+    };
+    let config = state.config.clone();
+
+    match config.peer_listen {
+        // peer routes are only served on the internal listener
+        Some(peer_listen) => {
+            let state = Arc::new(state);
+            tokio::join!(
+                serve(
+                    address,
+                    create_public_routes(state.clone()),
+                    server_config.clone(),
+                    config.clone(),
+                ),
+                serve(
+                    peer_listen,
+                    create_peer_routes(state),
+                    server_config,
+                    config.clone(),
+                ),
+            );
+        }
+        None => {
+            serve(
+                address,
+                routes::create_routes(state),
+                server_config,
+                config.clone(),
+            )
+            .await
+        }
+    }
+}
+
+// serves the routes on the address, over TLS if configured
+async fn serve(
+    address: SocketAddr,
+    app: Router,
+    server_config: Option<Arc<ServerConfig>>,
+    config: Arc<NodeConfig>,
+) {
+    event!(Level::DEBUG, "lib::serve {}", address);
+
+    match server_config {
+        Some(server_config) => match TcpListener::bind(address).await {
+            Ok(listener) => serve_tls(listener, app, server_config, config).await,
+            Err(err) => {
+                event!(Level::ERROR, "lib::serve::bind {} {}", address, err);
+                std::process::exit(1);
+            }
+        },
+        None => match axum::Server::try_bind(&address) {
+            Ok(server) => {
+                if let Err(err) = server
+                    .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                    .await
+                {
+                    event!(Level::ERROR, "lib::serve {} {}", address, err);
+                    std::process::exit(1);
+                }
+            }
+            Err(err) => {
+                event!(Level::ERROR, "lib::serve::bind {} {}", address, err);
+                std::process::exit(1);
+            }
+        },
+    }
 }
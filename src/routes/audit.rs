@@ -0,0 +1,90 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use tracing::{event, Level};
+
+use crate::cache::{
+    audit::{AuditQuery, AuditRecord, RequesterStats},
+    state::AppState,
+};
+
+// returns the most recent audit records of the openings that left the cache, matching the query
+pub async fn get_audit_records(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuditQuery>,
+) -> Json<Vec<AuditRecord>> {
+    event!(Level::DEBUG, "routes::audit::get_audit_records");
+    Json(state.audit.records(&query))
+}
+
+// returns the outcome of the sessions per requester, with the rate of withheld reveals
+pub async fn get_requester_stats(
+    State(state): State<Arc<AppState>>,
+) -> Json<HashMap<String, RequesterStats>> {
+    event!(Level::DEBUG, "routes::audit::get_requester_stats");
+    Json(state.audit.requester_stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{http::StatusCode, routing::get, Router};
+    use axum_test_helper::TestClient;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        cache::{
+            audit::EvictionCause,
+            state::{create_state, Role},
+        },
+        utils::config::NodeConfig,
+    };
+
+    #[tokio::test]
+    async fn test_audit_routes() {
+        let state = Arc::new(create_state(NodeConfig::default()).unwrap());
+        let commitment_id = Uuid::new_v4().as_u128();
+        state.audit.record(
+            commitment_id,
+            Role::Dealer,
+            "client:127.0.0.1",
+            EvictionCause::Expired,
+            0,
+        );
+        state
+            .audit
+            .record(1, Role::CoCommitter, "node:1", EvictionCause::Revealed, 0);
+
+        let client = TestClient::new(
+            Router::new()
+                .route("/audit", get(get_audit_records))
+                .route("/audit/requesters", get(get_requester_stats))
+                .with_state(state),
+        );
+
+        let res = client
+            .get(&format!("/audit?commitment_id={}", commitment_id))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let records: Vec<AuditRecord> = res.json().await;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].requester, "client:127.0.0.1");
+
+        let res = client.get("/audit?cause=revealed").send().await;
+        let records: Vec<AuditRecord> = res.json().await;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].role, Role::CoCommitter);
+
+        let res = client.get("/audit?cause=unknown").send().await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res = client.get("/audit/requesters").send().await;
+        let stats: HashMap<String, RequesterStats> = res.json().await;
+        assert_eq!(stats["client:127.0.0.1"].withheld_rate, 1.0);
+        assert_eq!(stats["node:1"].withheld_rate, 0.0);
+    }
+}
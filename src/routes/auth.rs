@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{event, Level};
+
+use crate::{cache::state::AppState, utils::tls::PeerCertificate};
+
+// over mutual TLS, rejects peer requests of connections without a peer certificate before reading
+// their body, the handlers check the certificate matches the node of the request. Without TLS the
+// peer routes are only served on the internal peer listener, which the configuration requires for
+// more than one node
+pub async fn require_peer<B>(
+    State(state): State<Arc<AppState>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, StatusCode> {
+    event!(Level::DEBUG, "routes::auth::require_peer");
+
+    if state.config.tls_enabled() && request.extensions().get::<PeerCertificate>().is_none() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(next.run(request).await)
+}
@@ -1,76 +1,195 @@
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
 use axum::{
-    extract::{Path, State},
+    extract::{rejection::JsonRejection, ConnectInfo, Path, Query, State},
     http::StatusCode,
-    Json,
+    Extension, Json,
 };
 
+use serde::Deserialize;
 use tracing::{event, Level};
 use uuid::Uuid;
 
 use crate::{
     cache::state::{
-        AppState, CommitmentForRandom, CommitmentForRandoms, CommittedRandom, CommittedRandomData,
+        AppState, CoCommitment, CommitRandomRequest, CommitmentForRandom, CommitmentForRandoms,
+        CommittedRandomData, ReleaseRequest, RevealRequest, RevealResponse, RevealedRandom, Role,
+        SessionProof,
     },
+    routes::proof::record_openings,
     utils::{
-        commitment::{Commitment, Opening},
-        config::get_node_id,
+        commitment::{Commitment, Opening, OpeningValue},
+        config::{NodeConfig, RandomMode},
         errors::CacheError,
-        peers::{get_commitment_from_peers, get_node_addresses},
-        random::generate_random,
+        identity::{
+            commitment_message, release_message, reveal_message, verify_auth_proof, AUTH_HASH_LEN,
+        },
+        peers::{
+            get_commitment_from_peers, get_node_addresses, get_peer_address,
+            release_co_commitments, verify_peer_signature,
+        },
+        random::{generate_random, generate_random_scalar},
+        tls::PeerCertificate,
     },
 };
 
-// generates u32 random and saves as u64 so that we don't overflow during addition of co-commitment
-async fn get_commitment_for_random() -> Result<(Commitment, Opening), StatusCode> {
+// generates u32 random and saves as u64 so that we don't overflow during addition of co-commitment,
+// or a uniformly random scalar in scalar mode
+async fn get_commitment_for_random(
+    random_mode: RandomMode,
+) -> Result<(Commitment, Opening), StatusCode> {
     event!(
         Level::DEBUG,
         "routes::commitment::get_commitment_for_random"
     );
 
+    if random_mode == RandomMode::Scalar {
+        let value = generate_random_scalar().map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Commitment::try_new_scalar(value)
+            .map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
     let random = generate_random(4).map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)?;
     let mut arr = [0; 4];
     arr.copy_from_slice(&random[0..4]);
     let value = u32::from_le_bytes(arr);
-    Ok(Commitment::new(value as u64))
+    Commitment::try_new(value as u64).map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+// proves the range of integer contributions, scalar contributions are aggregated mod the group order
+fn get_range_proof(
+    opening: &Opening,
+    commitment_id: u128,
+    node_id: u16,
+) -> Result<Vec<u8>, StatusCode> {
+    match opening.value {
+        OpeningValue::Integer(_) => opening
+            .prove_range(commitment_id, node_id)
+            .map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR),
+        OpeningValue::Scalar(_) => Ok(Vec::new()),
+    }
+}
+
+// checks the reveal window requested for a session against the maximum of the node
+fn get_reveal_deadline(
+    config: &NodeConfig,
+    reveal_deadline: Option<u64>,
+) -> Result<Option<Duration>, StatusCode> {
+    match reveal_deadline.map(Duration::from_secs) {
+        Some(deadline) if deadline.is_zero() || deadline > config.max_reveal_deadline() => {
+            Err(StatusCode::BAD_REQUEST)
+        }
+        deadline => Ok(deadline),
+    }
 }
 
-// stores commitment in cache
+// stores commitment in cache unless the commitment id is already taken, stored openings are never overwritten
 async fn store_commitment(
     commitment_id: Uuid,
+    role: Role,
     committed_random: CommittedRandomData,
     state: Arc<AppState>,
 ) -> Result<(), CacheError> {
     event!(
         Level::DEBUG,
-        "routes::commitment::store_commitment {}",
-        commitment_id
+        "routes::commitment::store_commitment {} {}",
+        commitment_id,
+        role
+    );
+
+    state
+        .cache
+        .insert((commitment_id, role), committed_random)
+        .await
+}
+
+// checks the auth hash of a session requested from the dealer
+fn check_auth_hash(auth_hash: Option<&Vec<u8>>) -> Result<(), StatusCode> {
+    match auth_hash {
+        Some(auth_hash) if auth_hash.len() != AUTH_HASH_LEN => Err(StatusCode::BAD_REQUEST),
+        _ => Ok(()),
+    }
+}
+
+// checks the request against the stored commitment, then marks it revealed, repeated reveals
+// return the same opening until the reveal retention elapses and the entry is purged. Without an
+// auth_hash anyone knowing the commitment id can replay the reveal within the retention, which only
+// discloses an opening already revealed, but lets third parties read it before the session proof
+pub(crate) async fn reveal_commitment(
+    role: Role,
+    request: &RevealRequest,
+    state: &AppState,
+) -> Result<CommittedRandomData, StatusCode> {
+    let commitment_id = Uuid::from_u128(request.commitment_id);
+    event!(
+        Level::DEBUG,
+        "routes::commitment::reveal_commitment {} {}",
+        commitment_id,
+        role
     );
 
-    state.cache.insert(commitment_id, committed_random).await;
-    Ok(())
+    let stored = state
+        .cache
+        .get((commitment_id, role))
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if !verify_auth_proof(stored.auth_hash.as_deref(), &request.auth_proof) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    // the client expects the opening of another commitment, e.g. from a dealer lying about the session
+    if request
+        .commitment
+        .as_ref()
+        .is_some_and(|commitment| *commitment != stored.commitment.to_bytes())
+    {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    state
+        .cache
+        .reveal((commitment_id, role))
+        .await
+        .ok_or(StatusCode::NOT_FOUND)
 }
 
 // returns addresses of all nodes
-pub async fn get_nodes() -> Result<Json<Vec<String>>, StatusCode> {
+pub async fn get_nodes(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<String>>, StatusCode> {
     event!(Level::DEBUG, "routes::commitment::get_nodes");
-    Ok(Json(get_node_addresses()))
+    Ok(Json(get_node_addresses(&state.config)))
 }
 
 // returns address of the node
-pub async fn get_node_address(Path(node_id): Path<u16>) -> Result<Json<String>, StatusCode> {
+pub async fn get_node_address(
+    State(state): State<Arc<AppState>>,
+    Path(node_id): Path<u16>,
+) -> Result<Json<String>, StatusCode> {
     event!(Level::DEBUG, "routes::commitment::get_node_address");
-    Ok(Json(crate::utils::peers::get_peer_address(node_id)))
+    get_peer_address(&state.config, node_id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
 }
 
 // commits to newly generated random, sends the request to other nodes to co-commit and returns aggregated commitment with nodes ids
 pub async fn commit_to_random(
     State(state): State<Arc<AppState>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    request: Result<Json<CommitRandomRequest>, JsonRejection>,
 ) -> Result<Json<CommitmentForRandoms>, StatusCode> {
     event!(Level::DEBUG, "routes::commitment::commit_to_random");
 
-    let (commitment, opening) = get_commitment_for_random().await?;
+    let request = match request {
+        Ok(Json(request)) => request,
+        // the request parameters are optional, so is the body
+        Err(JsonRejection::MissingJsonContentType(_)) => CommitRandomRequest::default(),
+        Err(rejection) => return Err(rejection.status()),
+    };
+    let reveal_deadline = get_reveal_deadline(&state.config, request.reveal_deadline)?;
+    check_auth_hash(request.auth_hash.as_ref())?;
+
+    let random_mode = state.config.random_mode;
+    let (commitment, opening) = get_commitment_for_random(random_mode).await?;
 
     let commitment_id = Uuid::new_v4();
     event!(
@@ -79,108 +198,357 @@ pub async fn commit_to_random(
         commitment_id
     );
 
+    let node_id = state.config.node_id;
+    let range_proof = get_range_proof(&opening, commitment_id.as_u128(), node_id)?;
+    let knowledge_proof = opening
+        .prove_knowledge(commitment_id.as_u128(), node_id)
+        .map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     store_commitment(
         commitment_id,
+        Role::Dealer,
         CommittedRandomData {
             commitment: commitment.clone(),
-            opening: opening,
+            opening,
+            co_commitment: None,
+            reveal_deadline,
+            requester: connect_info.map_or_else(
+                || "unknown".to_string(),
+                |ConnectInfo(address)| format!("client:{}", address.ip()),
+            ),
+            auth_hash: request.auth_hash.clone(),
         },
-        state,
+        state.clone(),
     )
     .await
     .map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let commitment_for_random = CommitmentForRandom {
-        node_id: get_node_id().parse::<u16>().unwrap(),
+    let mut commitment_for_random = CommitmentForRandom {
+        node_id,
         commitment_id: commitment_id.as_u128(),
         commitment: commitment.to_bytes(),
+        range_proof,
+        knowledge_proof,
+        signature: Vec::new(),
+        reveal_deadline: request.reveal_deadline,
+        auth_hash: request.auth_hash,
+        random_mode,
     };
+    commitment_for_random.signature = state.sign(&commitment_message(&commitment_for_random));
 
-    let co_commitments = get_commitment_from_peers(commitment_for_random.clone(), None)
+    let co_commitments = get_commitment_from_peers(
+        &state.config,
+        commitment_for_random.clone(),
+        Some(state.peer_client.clone()),
+    )
+    .await;
+
+    // the peers left out of the draw drop their co-commitments in the background, all of them if
+    // the quorum wasn't reached
+    let used: Vec<u16> = co_commitments
+        .iter()
+        .flatten()
+        .map(|co_commitment| co_commitment.node_id)
+        .collect();
+    let mut release = ReleaseRequest {
+        node_id,
+        commitment_id: commitment_id.as_u128(),
+        signature: Vec::new(),
+    };
+    release.signature = state.sign(&release_message(node_id, release.commitment_id));
+    let release_state = state.clone();
+    tokio::spawn(async move {
+        release_co_commitments(
+            &release_state.config,
+            release,
+            &used,
+            Some(release_state.peer_client.clone()),
+        )
         .await
-        .map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)?;
+    });
+
+    let co_commitments = co_commitments.map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     let mut aggregated_commitment = commitment.clone();
     let mut node_ids = Vec::new();
-    for co_commitment in co_commitments {
+    for co_commitment in &co_commitments {
         let peer_commitment = Commitment::from_slice(&co_commitment.commitment)
-            .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+            .map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)?;
         aggregated_commitment = aggregated_commitment + peer_commitment - commitment.clone(); // aggregate and remove dealer overcommitment
         node_ids.push(co_commitment.node_id);
     }
 
     node_ids.push(commitment_for_random.node_id); // adding dealer
 
-    Ok(Json(CommitmentForRandoms {
+    let commitment_for_randoms = CommitmentForRandoms {
         commitment_id: commitment_id.as_u128(),
         commitment: aggregated_commitment.to_bytes(),
-        node_ids: node_ids,
-        dealer_id: get_node_id().parse::<u16>().unwrap()
-    }))
+        node_ids,
+        dealer_id: node_id,
+    };
+
+    // keep the transcript of the draw for the proof endpoint
+    let mut commitments = vec![commitment_for_random];
+    commitments.extend(co_commitments);
+    state
+        .cache
+        .insert_proof(SessionProof {
+            commitment_id: commitment_for_randoms.commitment_id,
+            dealer_id: commitment_for_randoms.dealer_id,
+            node_ids: commitment_for_randoms.node_ids.clone(),
+            commitment: commitment_for_randoms.commitment.clone(),
+            commitments,
+            openings: Vec::new(),
+            random: None,
+            output: None,
+            random_mode,
+        })
+        .await?;
+
+    Ok(Json(commitment_for_randoms))
 }
 
 // co-commits to previous commitment and returns aggregated commitment with newly generated random
 pub async fn co_commit_to_random(
     State(state): State<Arc<AppState>>,
+    peer: Option<Extension<PeerCertificate>>,
     Json(previous_commitment): Json<CommitmentForRandom>,
 ) -> Result<Json<CommitmentForRandom>, StatusCode> {
     event!(Level::DEBUG, "routes::commitment::co_commit_to_random");
 
-    let (commitment, opening) = get_commitment_for_random().await?;
+    // over mutual TLS, the dealer has to be the peer its certificate was issued to
+    if state.config.tls_enabled()
+        && peer.map(|Extension(peer)| peer.node_id) != Some(previous_commitment.node_id)
+    {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if !verify_peer_signature(
+        &state.config,
+        previous_commitment.node_id,
+        &commitment_message(&previous_commitment),
+        &previous_commitment.signature,
+    ) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
     let commitment_bytes: &[u8] = &previous_commitment.commitment;
-    let co_commitment = commitment
-        + Commitment::from_slice(&commitment_bytes).ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    let dealer_commitment = Commitment::from_slice(commitment_bytes)?;
+
+    if !dealer_commitment.verify_knowledge(
+        &previous_commitment.knowledge_proof,
+        previous_commitment.commitment_id,
+        previous_commitment.node_id,
+    ) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
 
-    store_commitment(
-        Uuid::from_u128(previous_commitment.commitment_id),
+    // integer contributions of the dealer have to be proven in range before aggregation, the mode
+    // of the session is set by the dealer and covered by its signature
+    if previous_commitment.random_mode == RandomMode::Integer
+        && !dealer_commitment.verify_range(
+            &previous_commitment.range_proof,
+            previous_commitment.commitment_id,
+            previous_commitment.node_id,
+        )
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let reveal_deadline = get_reveal_deadline(&state.config, previous_commitment.reveal_deadline)?;
+    check_auth_hash(previous_commitment.auth_hash.as_ref())?;
+
+    // a taken commitment id is answered before generating an opening and its proofs
+    let key = Uuid::from_u128(previous_commitment.commitment_id);
+    if state.cache.contains((key, Role::CoCommitter)).await {
+        return replay_co_commitment(key, &previous_commitment, &state, CacheError::Taken).await;
+    }
+
+    let node_id = state.config.node_id;
+    let (commitment, opening) = get_commitment_for_random(previous_commitment.random_mode).await?;
+    let range_proof = get_range_proof(&opening, previous_commitment.commitment_id, node_id)?;
+    let knowledge_proof = opening
+        .prove_knowledge(previous_commitment.commitment_id, node_id)
+        .map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let co_commitment = commitment + dealer_commitment;
+
+    let mut response = CommitmentForRandom {
+        node_id,
+        commitment_id: previous_commitment.commitment_id,
+        commitment: co_commitment.to_bytes(),
+        range_proof,
+        knowledge_proof,
+        signature: Vec::new(),
+        reveal_deadline: None,
+        auth_hash: None,
+        random_mode: previous_commitment.random_mode,
+    };
+    response.signature = state.sign(&commitment_message(&response));
+
+    let stored = store_commitment(
+        key,
+        Role::CoCommitter,
         CommittedRandomData {
-            commitment: co_commitment.clone(),
-            opening: opening,
+            commitment: co_commitment,
+            opening,
+            co_commitment: Some(CoCommitment {
+                dealer_commitment: previous_commitment.clone(),
+                response: response.clone(),
+            }),
+            reveal_deadline,
+            requester: format!("node:{}", previous_commitment.node_id),
+            auth_hash: previous_commitment.auth_hash.clone(),
         },
-        state,
+        state.clone(),
     )
-    .await
-    .map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .await;
 
-    Ok(Json(CommitmentForRandom {
-        node_id: get_node_id().parse::<u16>().unwrap(),
-        commitment_id: previous_commitment.commitment_id,
-        commitment: co_commitment.to_bytes(),
-    }))
+    // a concurrent request may have taken the commitment id in the meantime
+    if let Err(err) = stored {
+        return replay_co_commitment(key, &previous_commitment, &state, err).await;
+    }
+
+    Ok(Json(response))
+}
+
+// drops the co-commitment of a session its dealer left out of the draw
+pub async fn release_co_commitment(
+    State(state): State<Arc<AppState>>,
+    peer: Option<Extension<PeerCertificate>>,
+    Json(request): Json<ReleaseRequest>,
+) -> Result<StatusCode, StatusCode> {
+    event!(Level::DEBUG, "routes::commitment::release_co_commitment");
+
+    // over mutual TLS, the dealer has to be the peer its certificate was issued to
+    if state.config.tls_enabled()
+        && peer.map(|Extension(peer)| peer.node_id) != Some(request.node_id)
+    {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if !verify_peer_signature(
+        &state.config,
+        request.node_id,
+        &release_message(request.node_id, request.commitment_id),
+        &request.signature,
+    ) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    // only an unrevealed co-commitment made for the dealer is released
+    let key = (Uuid::from_u128(request.commitment_id), Role::CoCommitter);
+    if !state
+        .cache
+        .release(key, format!("node:{}", request.node_id))
+        .await
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// a replay of the same dealer request, e.g. a retry after a timeout, gets the stored co-commitment,
+// any other request for a taken commitment id is rejected
+async fn replay_co_commitment(
+    key: Uuid,
+    previous_commitment: &CommitmentForRandom,
+    state: &AppState,
+    err: CacheError,
+) -> Result<Json<CommitmentForRandom>, StatusCode> {
+    match state
+        .cache
+        .get((key, Role::CoCommitter))
+        .await
+        .and_then(|data| data.co_commitment)
+    {
+        Some(stored) if stored.dealer_commitment == *previous_commitment => {
+            Ok(Json(stored.response))
+        }
+        _ => Err(err.into()),
+    }
+}
+
+// role of the opening to reveal, by default the dealer opening if the node dealt the session
+#[derive(Deserialize)]
+pub struct RevealQuery {
+    role: Option<Role>,
 }
 
 // reveals random opening for proofing and reconstruction
 pub async fn reveal_random(
     State(state): State<Arc<AppState>>,
-    Json(commitment): Json<CommitmentForRandom>,
-) -> Result<Json<CommittedRandom>, StatusCode> {
-    event!(Level::DEBUG, "routes::commitment::get_commitment");
-
-    let key = Uuid::from_u128(commitment.commitment_id);
-    let value = state.cache.get(&key).await.ok_or(StatusCode::NOT_FOUND)?;
+    Query(query): Query<RevealQuery>,
+    Json(request): Json<RevealRequest>,
+) -> Result<Json<RevealResponse>, StatusCode> {
+    event!(Level::DEBUG, "routes::commitment::reveal_random");
+
+    let commitment_id = request.commitment_id;
+    let role = match query.role {
+        Some(role) => role,
+        None if state
+            .cache
+            .contains((Uuid::from_u128(commitment_id), Role::Dealer))
+            .await =>
+        {
+            Role::Dealer
+        }
+        None => Role::CoCommitter,
+    };
+    let value = reveal_commitment(role, &request, &state).await?;
 
-    // invalidate cache
-    state.cache.invalidate(&key).await;
+    let node_id = state.config.node_id;
+    let commitment_bytes = value.commitment.to_bytes();
+    let opening_bytes = value.opening.to_bytes();
+    let signature = state.sign(&reveal_message(
+        node_id,
+        commitment_id,
+        role,
+        &commitment_bytes,
+        &opening_bytes,
+    ));
+
+    // the dealer records its own opening in the session proof
+    let proof = match role {
+        Role::Dealer => {
+            record_openings(
+                commitment_id,
+                vec![RevealedRandom {
+                    node_id,
+                    commitment: commitment_bytes.clone(),
+                    opening: opening_bytes.clone(),
+                    signature: signature.clone(),
+                }],
+                &state,
+            )
+            .await
+        }
+        Role::CoCommitter => None,
+    };
 
-    Ok(Json(CommittedRandom {
-        commitment: value.commitment.to_bytes(),
-        opening: value.opening.to_bytes(),
+    Ok(Json(RevealResponse {
+        node_id,
+        commitment_id,
+        commitment: commitment_bytes,
+        opening: opening_bytes,
+        signature,
+        role: Some(role),
+        output: proof.and_then(|proof| proof.output),
     }))
 }
 
 #[cfg(test)]
 mod tests {
 
-    use std::env::set_var;
-
     use crate::{
         cache::state::create_state,
         utils::{
-            config::{get_mpc_threshold, get_peer_count},
+            config::PeerConfig,
+            identity::{auth_hash, verify_signature, NodeIdentity},
             peers::{
-                get_commit_to_random_endpoint, get_nodes_endpoint, get_peer_port,
-                get_reveal_random_endpoint,
+                get_commit_to_random_endpoint, get_nodes_endpoint, get_reveal_random_endpoint,
+                get_session_proof_path,
             },
+            transcript::verify_transcript,
         },
     };
     use axum::{routing::post, Router};
@@ -190,24 +558,28 @@ mod tests {
 
     use super::*;
 
+    fn create_test_state() -> AppState {
+        create_state(NodeConfig {
+            node_id: 5,
+            num_nodes: 5,
+            ..NodeConfig::default()
+        })
+        .unwrap()
+    }
+
     #[tokio::test]
     async fn test_co_commit_to_random() {
         let random1 = 123124;
         let (commitment1, opening1) = Commitment::new(random1);
 
-        let node_1_commitment = CommitmentForRandom {
-            node_id: 1,
-            commitment_id: 123 as u128,
-            commitment: commitment1.to_bytes(),
-        };
+        let node_1_commitment = CommitmentForRandom::for_test(1, 123, &commitment1, &opening1);
 
-        let state = create_state();
+        let state = create_test_state();
         let shared_state = Arc::new(state);
         let app = Router::new()
             .route("/co-commit-random", post(co_commit_to_random))
             .with_state(shared_state.clone());
 
-        set_var("NODE_ID", "5");
         let commitment_str = serde_json::to_string(&node_1_commitment).unwrap();
         let res = TestClient::new(app)
             .post("/co-commit-random")
@@ -222,7 +594,11 @@ mod tests {
         assert_eq!(co_commitment_response.node_id, 5);
 
         let key = Uuid::from_u128(node_1_commitment.commitment_id);
-        let value = shared_state.cache.get(&key).await.unwrap();
+        let value = shared_state
+            .cache
+            .get((key, Role::CoCommitter))
+            .await
+            .unwrap();
 
         // validate cache
         assert_eq!(co_commitment_from_response, value.commitment);
@@ -242,17 +618,413 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_reveal_random() {
-        let random1 = 123124;
-        let (commitment1, _opening1) = Commitment::new(random1);
+    async fn test_co_commit_to_random_reveal_deadline() {
+        let (commitment1, opening1) = Commitment::new(123124);
+        let mut node_1_commitment = CommitmentForRandom {
+            reveal_deadline: Some(2 * 60 * 60),
+            ..CommitmentForRandom::for_test(1, 130, &commitment1, &opening1)
+        };
+
+        let shared_state = Arc::new(create_test_state());
+        let app = Router::new()
+            .route("/co-commit-random", post(co_commit_to_random))
+            .with_state(shared_state.clone());
+        let client = TestClient::new(app);
+
+        let res = client
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let key = (Uuid::from_u128(130), Role::CoCommitter);
+        let value = shared_state.cache.get(key).await.unwrap();
+        assert_eq!(
+            value.reveal_deadline,
+            Some(Duration::from_secs(2 * 60 * 60))
+        );
+
+        // the reveal window is bounded by the maximum of the node
+        node_1_commitment.commitment_id = 131;
+        node_1_commitment.range_proof = opening1.prove_range(131, 1).unwrap();
+        node_1_commitment.knowledge_proof = opening1.prove_knowledge(131, 1).unwrap();
+        node_1_commitment.reveal_deadline = Some(shared_state.config.max_reveal_deadline_secs + 1);
+        let res = client
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_commit_to_random_invalid_reveal_deadline() {
+        let app = Router::new()
+            .route("/commit-random", post(commit_to_random))
+            .with_state(Arc::new(create_test_state()));
+        let client = TestClient::new(app);
+
+        for reveal_deadline in [0, 24 * 60 * 60 + 1] {
+            let res = client
+                .post("/commit-random")
+                .json(&CommitRandomRequest {
+                    reveal_deadline: Some(reveal_deadline),
+                    auth_hash: None,
+                })
+                .send()
+                .await;
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        }
+
+        let res = client
+            .post("/commit-random")
+            .header("content-type", "application/json")
+            .body("{\"reveal_deadline\": \"soon\"}")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_co_commit_to_random_invalid_range_proof() {
+        let (commitment1, opening1) = Commitment::new(123124);
+        let (_, other_opening) = Commitment::new(123124);
+
+        let node_1_commitment = CommitmentForRandom {
+            range_proof: other_opening.prove_range(124, 1).unwrap(),
+            ..CommitmentForRandom::for_test(1, 124, &commitment1, &opening1)
+        };
+
+        let state = create_test_state();
+        let shared_state = Arc::new(state);
+        let app = Router::new()
+            .route("/co-commit-random", post(co_commit_to_random))
+            .with_state(shared_state.clone());
+
+        let res = TestClient::new(app)
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert!(
+            !shared_state
+                .cache
+                .contains((
+                    Uuid::from_u128(node_1_commitment.commitment_id),
+                    Role::CoCommitter
+                ))
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_co_commit_to_random_session_mode() {
+        let value1 = generate_random_scalar().unwrap();
+        let (commitment1, opening1) = Commitment::new_scalar(value1);
+        let node_1_commitment = CommitmentForRandom::for_test(1, 125, &commitment1, &opening1);
 
+        // the contribution of an integer node follows the mode of the session set by the dealer
+        let shared_state = Arc::new(create_test_state());
+        let app = Router::new()
+            .route("/co-commit-random", post(co_commit_to_random))
+            .with_state(shared_state.clone());
+        let res = TestClient::new(app)
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+        let response: CommitmentForRandom = res.json().await;
+        assert_eq!(response.random_mode, RandomMode::Scalar);
+        assert!(response.range_proof.is_empty());
+        let value = shared_state
+            .cache
+            .get((Uuid::from_u128(125), Role::CoCommitter))
+            .await
+            .unwrap();
+        assert_eq!(value.opening.value.mode(), RandomMode::Scalar);
+
+        // integer sessions need the range proof of the dealer, whatever the mode of the node
+        let (commitment1, opening1) = Commitment::new(123124);
         let node_1_commitment = CommitmentForRandom {
-            node_id: 1,
-            commitment_id: 123 as u128,
+            commitment_id: 126_u128,
             commitment: commitment1.to_bytes(),
+            knowledge_proof: opening1.prove_knowledge(126, 1).unwrap(),
+            random_mode: RandomMode::Integer,
+            ..node_1_commitment
+        };
+        let app = Router::new()
+            .route("/co-commit-random", post(co_commit_to_random))
+            .with_state(Arc::new(
+                create_state(NodeConfig {
+                    random_mode: RandomMode::Scalar,
+                    ..NodeConfig::default()
+                })
+                .unwrap(),
+            ));
+        let res = TestClient::new(app)
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_co_commit_to_random_rogue_commitment() {
+        let (commitment1, opening1) = Commitment::new(123124);
+        let (commitment2, _) = Commitment::new(123124);
+
+        // commitment derived from another node's commitment, with a proof for the own part only
+        let node_1_commitment =
+            CommitmentForRandom::for_test(1, 125, &(commitment1 - commitment2), &opening1);
+
+        let app = Router::new()
+            .route("/co-commit-random", post(co_commit_to_random))
+            .with_state(Arc::new(create_test_state()));
+
+        let res = TestClient::new(app)
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_co_commit_to_random_signed() {
+        let dealer = NodeIdentity::from_pkcs8(&NodeIdentity::generate().unwrap()).unwrap();
+        let document = NodeIdentity::generate().unwrap();
+        let node = NodeIdentity::from_pkcs8(&document).unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "random-pedersen-test-co-commit-{}.key",
+            Uuid::new_v4()
+        ));
+        std::fs::write(&path, &document).unwrap();
+
+        let state = create_state(NodeConfig {
+            node_id: 5,
+            num_nodes: 2,
+            peers: vec![
+                PeerConfig {
+                    id: 1,
+                    url: "http://node-1:7000".to_string(),
+                    public_key: Some(hex::encode(dealer.public_key())),
+                    peer_url: None,
+                },
+                PeerConfig {
+                    id: 5,
+                    url: "http://node-5:7000".to_string(),
+                    public_key: Some(hex::encode(node.public_key())),
+                    peer_url: None,
+                },
+            ],
+            signing_key_file: Some(path),
+            ..NodeConfig::default()
+        })
+        .unwrap();
+        let client = TestClient::new(
+            Router::new()
+                .route("/co-commit-random", post(co_commit_to_random))
+                .with_state(Arc::new(state)),
+        );
+
+        let (commitment1, opening1) = Commitment::new(123124);
+        let mut node_1_commitment = CommitmentForRandom::for_test(1, 127, &commitment1, &opening1);
+
+        // unsigned commitments of a node with a public key are rejected
+        let res = client
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        node_1_commitment.signature = dealer.sign(&commitment_message(&node_1_commitment));
+        let res = client
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+        let co_commitment_response: CommitmentForRandom = res.json().await;
+
+        assert!(verify_signature(
+            &node.public_key(),
+            &commitment_message(&co_commitment_response),
+            &co_commitment_response.signature
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_co_commit_to_random_peer_certificate() {
+        let (commitment1, opening1) = Commitment::new(123124);
+        let node_1_commitment = CommitmentForRandom::for_test(1, 128, &commitment1, &opening1);
+
+        // the certificate files are only loaded by the listener and the peer client
+        let mut state = create_test_state();
+        state.config = Arc::new(NodeConfig {
+            tls_ca_file: Some("ca.pem".into()),
+            tls_cert_file: Some("node-5.pem".into()),
+            tls_key_file: Some("node-5.key".into()),
+            ..(*state.config).clone()
+        });
+        let state = Arc::new(state);
+        let client = |peer: Option<u16>| {
+            let app = Router::new()
+                .route("/co-commit-random", post(co_commit_to_random))
+                .with_state(state.clone());
+            TestClient::new(match peer {
+                Some(node_id) => app.layer(Extension(PeerCertificate { node_id })),
+                None => app,
+            })
+        };
+
+        for (peer, status) in [
+            (None, StatusCode::FORBIDDEN),
+            (Some(2), StatusCode::FORBIDDEN),
+            (Some(1), StatusCode::OK),
+        ] {
+            let res = client(peer)
+                .post("/co-commit-random")
+                .json(&node_1_commitment)
+                .send()
+                .await;
+            assert_eq!(res.status(), status);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_co_commit_to_random_replay() {
+        let dealer_commitment = |random: u64| {
+            let (commitment, opening) = Commitment::new(random);
+            CommitmentForRandom::for_test(1, 129, &commitment, &opening)
+        };
+        let node_1_commitment = dealer_commitment(123124);
+
+        let state = Arc::new(create_test_state());
+        let client = TestClient::new(
+            Router::new()
+                .route("/co-commit-random", post(co_commit_to_random))
+                .with_state(state.clone()),
+        );
+
+        let res = client
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+        let co_commitment_response: CommitmentForRandom = res.json().await;
+
+        // a replay of the same request gets the same co-commitment
+        let res = client
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let replay_response: CommitmentForRandom = res.json().await;
+        assert_eq!(replay_response, co_commitment_response);
+
+        // another commitment for the same id doesn't overwrite the stored opening
+        let res = client
+            .post("/co-commit-random")
+            .json(&dealer_commitment(5))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+
+        let request = RevealRequest {
+            commitment_id: 129,
+            ..RevealRequest::default()
+        };
+        let stored = reveal_commitment(Role::CoCommitter, &request, &state)
+            .await
+            .unwrap();
+        assert_eq!(
+            stored.commitment.to_bytes(),
+            co_commitment_response.commitment
+        );
+    }
+
+    #[tokio::test]
+    async fn test_release_co_commitment() {
+        let (commitment1, opening1) = Commitment::new(123124);
+        let node_1_commitment = CommitmentForRandom::for_test(1, 133, &commitment1, &opening1);
+
+        let shared_state = Arc::new(create_test_state());
+        let app = Router::new()
+            .route("/co-commit-random", post(co_commit_to_random))
+            .route("/co-commit-random/release", post(release_co_commitment))
+            .with_state(shared_state.clone());
+        let client = TestClient::new(app);
+
+        let res = client
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // only the dealer the co-commitment was made for releases it
+        let mut release = ReleaseRequest {
+            node_id: 2,
+            commitment_id: 133,
+            signature: Vec::new(),
+        };
+        let res = client
+            .post("/co-commit-random/release")
+            .json(&release)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        release.node_id = 1;
+        let res = client
+            .post("/co-commit-random/release")
+            .json(&release)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        let key = (Uuid::from_u128(133), Role::CoCommitter);
+        assert!(!shared_state.cache.contains(key).await);
+
+        // the released co-commitment isn't withheld by the dealer
+        let stats = shared_state.audit.requester_stats();
+        assert_eq!(stats["node:1"].released, 1);
+        assert_eq!(stats["node:1"].withheld_rate, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_co_commit_to_random_invalid_commitment() {
+        let (commitment1, opening1) = Commitment::new(123124);
+        let node_1_commitment = CommitmentForRandom {
+            commitment: vec![0xff_u8; 32],
+            ..CommitmentForRandom::for_test(1, 126, &commitment1, &opening1)
         };
 
-        let state = create_state();
+        let app = Router::new()
+            .route("/co-commit-random", post(co_commit_to_random))
+            .with_state(Arc::new(create_test_state()));
+
+        let res = TestClient::new(app)
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_reveal_random() {
+        let random1 = 123124;
+        let (commitment1, opening1) = Commitment::new(random1);
+
+        let node_1_commitment = CommitmentForRandom::for_test(1, 123, &commitment1, &opening1);
+
+        let state = create_test_state();
         let shared_state = Arc::new(state);
         let app1 = Router::new()
             .route("/co-commit-random", post(co_commit_to_random))
@@ -260,7 +1032,6 @@ mod tests {
             .with_state(shared_state.clone());
         let client = TestClient::new(app1);
 
-        set_var("NODE_ID", "5");
         let mut commitment_str = serde_json::to_string(&node_1_commitment).unwrap();
         let res1 = client
             .post("/co-commit-random")
@@ -271,9 +1042,14 @@ mod tests {
 
         let co_commitment_response: CommitmentForRandom = res1.json().await;
         let key = Uuid::from_u128(co_commitment_response.commitment_id);
-        assert_eq!(shared_state.cache.contains_key(&key), true); // should exist
-
-        commitment_str = serde_json::to_string(&co_commitment_response).unwrap();
+        assert!(shared_state.cache.contains((key, Role::CoCommitter)).await); // should exist
+
+        commitment_str = serde_json::to_string(&RevealRequest {
+            commitment_id: co_commitment_response.commitment_id,
+            commitment: Some(co_commitment_response.commitment.clone()),
+            ..RevealRequest::default()
+        })
+        .unwrap();
         let res2 = client
             .post("/reveal-random")
             .header("content-type", "application/json")
@@ -281,8 +1057,8 @@ mod tests {
             .send()
             .await;
 
-        assert_eq!(shared_state.cache.contains_key(&key), false); // shouldn't exist
-        let random2_response: CommittedRandom = res2.json().await;
+        assert!(shared_state.cache.contains((key, Role::CoCommitter)).await); // kept for repeated reveals
+        let random2_response: RevealResponse = res2.json().await;
 
         // validate cache and response
         let co_commitment_from_response =
@@ -298,27 +1074,191 @@ mod tests {
         assert_eq!(aggregated_commitment, co_commitment_from_response);
     }
 
-    fn get_peer_address_mock(index: u16) -> String {
-        format!("http://127.0.0.1:{}", get_peer_port_mock(index))
+    #[tokio::test]
+    async fn test_reveal_random_auth_proof() {
+        let (commitment1, opening1) = Commitment::new(123124);
+        let mut node_1_commitment = CommitmentForRandom {
+            auth_hash: Some(auth_hash(b"secret")),
+            ..CommitmentForRandom::for_test(1, 132, &commitment1, &opening1)
+        };
+
+        let shared_state = Arc::new(create_test_state());
+        let app = Router::new()
+            .route("/co-commit-random", post(co_commit_to_random))
+            .route("/reveal-random", post(reveal_random))
+            .with_state(shared_state.clone());
+        let client = TestClient::new(app);
+
+        let res = client
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let co_commitment_response: CommitmentForRandom = res.json().await;
+
+        // the opening is only revealed to requests proving the secret of the session
+        for auth_proof in [Vec::new(), b"guess".to_vec()] {
+            let res = client
+                .post("/reveal-random")
+                .json(&RevealRequest {
+                    commitment_id: 132,
+                    auth_proof,
+                    commitment: None,
+                })
+                .send()
+                .await;
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        // nor to requests expecting another commitment
+        let res = client
+            .post("/reveal-random")
+            .json(&RevealRequest {
+                commitment_id: 132,
+                auth_proof: b"secret".to_vec(),
+                commitment: Some(commitment1.to_bytes()),
+            })
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        assert!(shared_state
+            .cache
+            .get((Uuid::from_u128(132), Role::CoCommitter))
+            .await
+            .is_some());
+
+        let res = client
+            .post("/reveal-random")
+            .json(&RevealRequest {
+                commitment_id: 132,
+                auth_proof: b"secret".to_vec(),
+                commitment: Some(co_commitment_response.commitment.clone()),
+            })
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let revealed: RevealResponse = res.json().await;
+        assert_eq!(revealed.commitment, co_commitment_response.commitment);
+
+        // the auth hash has to be a SHA-256 hash
+        node_1_commitment.commitment_id = 133;
+        node_1_commitment.range_proof = opening1.prove_range(133, 1).unwrap();
+        node_1_commitment.knowledge_proof = opening1.prove_knowledge(133, 1).unwrap();
+        node_1_commitment.auth_hash = Some(b"secret".to_vec());
+        let res = client
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_reveal_random_both_roles() {
+        let (commitment1, opening1) = Commitment::new(123124);
+        let (dealer_commitment, dealer_opening) = Commitment::new(456);
+
+        let node_1_commitment = CommitmentForRandom::for_test(1, 126, &commitment1, &opening1);
+
+        // the node already holds a dealer entry for the same session
+        let shared_state = Arc::new(create_test_state());
+        let key = Uuid::from_u128(node_1_commitment.commitment_id);
+        store_commitment(
+            key,
+            Role::Dealer,
+            CommittedRandomData {
+                commitment: dealer_commitment.clone(),
+                opening: dealer_opening,
+                co_commitment: None,
+                reveal_deadline: None,
+                requester: "client:127.0.0.1".to_string(),
+                auth_hash: None,
+            },
+            shared_state.clone(),
+        )
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/co-commit-random", post(co_commit_to_random))
+            .route("/reveal-random", post(reveal_random))
+            .with_state(shared_state.clone());
+        let client = TestClient::new(app);
+
+        let res = client
+            .post("/co-commit-random")
+            .json(&node_1_commitment)
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let co_commitment_response: CommitmentForRandom = res.json().await;
+        let request = RevealRequest {
+            commitment_id: co_commitment_response.commitment_id,
+            ..RevealRequest::default()
+        };
+
+        let res = client
+            .post("/reveal-random?role=co-committer")
+            .json(&request)
+            .send()
+            .await;
+        let revealed: RevealResponse = res.json().await;
+        assert_eq!(revealed.node_id, 5);
+        assert_eq!(revealed.commitment_id, co_commitment_response.commitment_id);
+        assert_eq!(revealed.role, Some(Role::CoCommitter));
+        assert_eq!(revealed.commitment, co_commitment_response.commitment);
+
+        // a repeated reveal returns the same opening, and the dealer entry is left untouched
+        let res = client
+            .post("/reveal-random?role=co-committer")
+            .json(&request)
+            .send()
+            .await;
+        let repeated: RevealResponse = res.json().await;
+        assert_eq!(repeated.opening, revealed.opening);
+        assert_eq!(repeated.signature, revealed.signature);
+        let dealer_entry = shared_state.cache.get((key, Role::Dealer)).await.unwrap();
+        assert_eq!(dealer_entry.commitment, dealer_commitment);
+
+        let res = client
+            .post("/reveal-random?role=dealer")
+            .json(&request)
+            .send()
+            .await;
+        let revealed: RevealResponse = res.json().await;
+        assert_eq!(revealed.role, Some(Role::Dealer));
+        assert_eq!(revealed.commitment, dealer_commitment.to_bytes());
+    }
+
+    fn get_peer_address_mock(config: &NodeConfig, index: u16) -> String {
+        format!("http://127.0.0.1:{}", get_peer_port_mock(config, index))
     }
 
-    fn get_peer_port_mock(node_number: u16) -> u16 {
-        get_peer_port(node_number) + node_number
+    fn get_peer_port_mock(config: &NodeConfig, node_number: u16) -> u16 {
+        config.port + node_number
     }
 
     #[test]
     #[ignore]
     fn test_e2e_get_nodes() {
-        set_var("NUM_NODES", "3");
+        let config = NodeConfig {
+            num_nodes: 3,
+            ..NodeConfig::default()
+        };
 
         let mut responses = Vec::new();
-        let num_nodes = get_peer_count().parse::<u16>().unwrap();
+        let num_nodes = config.num_nodes;
         let client = Client::new();
 
         let mut response_object: Vec<String> = Vec::new();
 
         for index in 1..num_nodes + 1 {
-            let address = format!("{}{}", get_peer_address_mock(index), get_nodes_endpoint());
+            let address = format!(
+                "{}{}",
+                get_peer_address_mock(&config, index),
+                get_nodes_endpoint()
+            );
             let response = client.get(address).send().unwrap();
 
             response_object = response.json::<Vec<String>>().unwrap();
@@ -339,25 +1279,23 @@ mod tests {
     #[test]
     #[ignore]
     fn test_e2e_commit_reveal() {
-        set_var("NUM_NODES", "2");
+        let config = NodeConfig::default();
 
         let mut responses = Vec::new();
-        let num_nodes = get_peer_count().parse::<u16>().unwrap();
         let client = Client::new();
 
         let address = format!(
             "{}{}",
-            get_peer_address_mock(1),
+            get_peer_address_mock(&config, 1),
             get_commit_to_random_endpoint()
         );
         let response = client.post(address).send().unwrap();
 
         let response_object = response.json::<CommitmentForRandoms>().unwrap();
 
-        let threshold = (get_mpc_threshold().parse::<f32>().unwrap() * num_nodes as f32).floor();
-        assert_ge!(response_object.node_ids.len() as f32, threshold);
+        assert_ge!(response_object.node_ids.len(), config.quorum());
 
-        let mut aggr_value = 0;
+        let mut aggr_value = OpeningValue::Integer(0);
         let mut aggr_opening: Option<Opening> = None;
         let mut aggr_commitment: Option<Commitment> = None;
         let mut dealer_commitment: Option<Commitment> = None;
@@ -365,26 +1303,25 @@ mod tests {
         for node_id in &response_object.node_ids {
             let node_address = format!(
                 "{}{}",
-                get_peer_address_mock(*node_id),
+                get_peer_address_mock(&config, *node_id),
                 get_reveal_random_endpoint()
             );
 
             let node_response = client
                 .post(node_address)
-                .json(&CommitmentForRandom {
+                .json(&RevealRequest {
                     commitment_id: response_object.commitment_id,
-                    commitment: Vec::new(),
-                    node_id: 0,
+                    ..RevealRequest::default()
                 })
                 .send()
                 .unwrap();
 
-            let response_node_object = node_response.json::<CommittedRandom>().unwrap();
+            let response_node_object = node_response.json::<RevealResponse>().unwrap();
             responses.push(response_node_object.clone());
 
             let node_opening = Opening::from_slice(&response_node_object.opening).unwrap();
             let node_commitment = Commitment::from_slice(&response_node_object.commitment).unwrap();
-            aggr_value += node_opening.value;
+            aggr_value = aggr_value + node_opening.value;
 
             if aggr_opening.is_some() {
                 aggr_opening = Some(aggr_opening.unwrap() + node_opening);
@@ -418,5 +1355,22 @@ mod tests {
             aggr_commitment.unwrap(),
             Commitment::from_slice(&response_object.commitment).unwrap()
         );
+
+        // verifying the transcript finalized by the dealer offline, it records the commitment of
+        // every participant
+        let address = format!(
+            "{}{}",
+            get_peer_address_mock(&config, response_object.dealer_id),
+            get_session_proof_path(response_object.commitment_id)
+        );
+        let proof = client
+            .post(address)
+            .send()
+            .unwrap()
+            .json::<SessionProof>()
+            .unwrap();
+        assert_eq!(proof.node_ids, response_object.node_ids);
+        assert_eq!(proof.random, Some(aggr_value));
+        assert_eq!(verify_transcript(&proof).unwrap().value, aggr_value);
     }
 }
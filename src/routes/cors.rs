@@ -1,7 +1,7 @@
 use axum::http::Method;
 use tower_http::cors::{Any, CorsLayer};
 
-// needs to be extended to allow requests from peers only for co-commitment
+// applies to the client routes only, peers are authenticated by their certificates over mutual TLS
 pub fn get_cors() -> CorsLayer {
     CorsLayer::new()
         .allow_methods([Method::GET, Method::POST])
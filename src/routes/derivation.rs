@@ -0,0 +1,334 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Deserialize;
+use tracing::{event, Level};
+
+use crate::{
+    cache::state::{AppState, DerivedRandoms, DerivedStream},
+    routes::proof::get_session_output,
+    utils::derivation::{
+        derive_range, derive_sample, derive_shuffle, derive_stream, derive_weighted,
+    },
+};
+
+// upper bound of derived items per request
+const MAX_DERIVED_ITEMS: u64 = 100_000;
+// upper bound of stream bytes per request
+const MAX_STREAM_LENGTH: usize = 64 * 1024;
+
+#[derive(Deserialize)]
+pub struct RangeQuery {
+    label: String,
+    lo: u64,
+    hi: u64,
+}
+
+#[derive(Deserialize)]
+pub struct ShuffleQuery {
+    label: String,
+    n: u64,
+}
+
+#[derive(Deserialize)]
+pub struct SampleQuery {
+    label: String,
+    n: u64,
+    k: u64,
+}
+
+#[derive(Deserialize)]
+pub struct StreamQuery {
+    label: String,
+    #[serde(default)]
+    offset: u64,
+    len: usize,
+}
+
+#[derive(Deserialize)]
+pub struct WeightedQuery {
+    label: String,
+    // comma separated list of weights
+    weights: String,
+}
+
+// derives an unbiased integer in [lo, hi) from the session output
+pub async fn get_session_range(
+    State(state): State<Arc<AppState>>,
+    Path(commitment_id): Path<u128>,
+    Query(query): Query<RangeQuery>,
+) -> Result<Json<DerivedRandoms>, StatusCode> {
+    event!(Level::DEBUG, "routes::derivation::get_session_range");
+
+    let output = get_session_output(commitment_id, &state).await?;
+    let value = derive_range(&output, &query.label, query.lo, query.hi)?;
+
+    Ok(Json(DerivedRandoms {
+        commitment_id,
+        label: query.label,
+        values: vec![value],
+    }))
+}
+
+// derives a permutation of n items from the session output
+pub async fn get_session_shuffle(
+    State(state): State<Arc<AppState>>,
+    Path(commitment_id): Path<u128>,
+    Query(query): Query<ShuffleQuery>,
+) -> Result<Json<DerivedRandoms>, StatusCode> {
+    event!(Level::DEBUG, "routes::derivation::get_session_shuffle");
+
+    if query.n > MAX_DERIVED_ITEMS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let output = get_session_output(commitment_id, &state).await?;
+    Ok(Json(DerivedRandoms {
+        commitment_id,
+        values: derive_shuffle(&output, &query.label, query.n),
+        label: query.label,
+    }))
+}
+
+// derives k of n items without replacement from the session output
+pub async fn get_session_sample(
+    State(state): State<Arc<AppState>>,
+    Path(commitment_id): Path<u128>,
+    Query(query): Query<SampleQuery>,
+) -> Result<Json<DerivedRandoms>, StatusCode> {
+    event!(Level::DEBUG, "routes::derivation::get_session_sample");
+
+    if query.k > MAX_DERIVED_ITEMS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let output = get_session_output(commitment_id, &state).await?;
+    Ok(Json(DerivedRandoms {
+        commitment_id,
+        values: derive_sample(&output, &query.label, query.n, query.k)?,
+        label: query.label,
+    }))
+}
+
+// derives the index of a weighted choice from the session output
+pub async fn get_session_weighted(
+    State(state): State<Arc<AppState>>,
+    Path(commitment_id): Path<u128>,
+    Query(query): Query<WeightedQuery>,
+) -> Result<Json<DerivedRandoms>, StatusCode> {
+    event!(Level::DEBUG, "routes::derivation::get_session_weighted");
+
+    let weights = query
+        .weights
+        .split(',')
+        .map(|weight| weight.trim().parse::<u64>())
+        .collect::<Result<Vec<u64>, _>>()
+        .map_err(|_error| StatusCode::BAD_REQUEST)?;
+    if weights.len() as u64 > MAX_DERIVED_ITEMS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let output = get_session_output(commitment_id, &state).await?;
+    let index = derive_weighted(&output, &query.label, &weights)?;
+
+    Ok(Json(DerivedRandoms {
+        commitment_id,
+        label: query.label,
+        values: vec![index as u64],
+    }))
+}
+
+// returns a window of the session stream for the label, reproducible by any verifier
+pub async fn get_session_stream(
+    State(state): State<Arc<AppState>>,
+    Path(commitment_id): Path<u128>,
+    Query(query): Query<StreamQuery>,
+) -> Result<Json<DerivedStream>, StatusCode> {
+    event!(Level::DEBUG, "routes::derivation::get_session_stream");
+
+    if query.len > MAX_STREAM_LENGTH {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let output = get_session_output(commitment_id, &state).await?;
+    Ok(Json(DerivedStream {
+        commitment_id,
+        offset: query.offset,
+        bytes: derive_stream(&output, &query.label, query.offset, query.len),
+        label: query.label,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::get, Router};
+    use axum_test_helper::TestClient;
+
+    use crate::{
+        cache::state::{create_state, SessionProof},
+        utils::config::{NodeConfig, RandomMode},
+    };
+
+    use super::*;
+
+    async fn create_app(output: Option<Vec<u8>>) -> Router {
+        let state = create_state(NodeConfig::default()).unwrap();
+        state
+            .cache
+            .insert_proof(SessionProof {
+                commitment_id: 123,
+                dealer_id: 1,
+                node_ids: vec![1],
+                commitment: Vec::new(),
+                commitments: Vec::new(),
+                openings: Vec::new(),
+                random: None,
+                output,
+                random_mode: RandomMode::Integer,
+            })
+            .await
+            .unwrap();
+
+        Router::new()
+            .route("/session/:commitment_id/range", get(get_session_range))
+            .route("/session/:commitment_id/shuffle", get(get_session_shuffle))
+            .route("/session/:commitment_id/sample", get(get_session_sample))
+            .route(
+                "/session/:commitment_id/weighted",
+                get(get_session_weighted),
+            )
+            .route("/session/:commitment_id/stream", get(get_session_stream))
+            .with_state(Arc::new(state))
+    }
+
+    #[tokio::test]
+    async fn test_get_session_range() {
+        let output = vec![7_u8; 32];
+        let client = TestClient::new(create_app(Some(output.clone())).await);
+
+        let res = client
+            .get("/session/123/range?label=raffle&lo=10&hi=20")
+            .send()
+            .await;
+        let derived: DerivedRandoms = res.json().await;
+
+        assert_eq!(derived.label, "raffle");
+        assert_eq!(
+            derived.values,
+            vec![derive_range(&output, "raffle", 10, 20).unwrap()]
+        );
+
+        let res = client
+            .get("/session/123/range?label=raffle&lo=20&hi=10")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_get_session_shuffle() {
+        let output = vec![7_u8; 32];
+        let client = TestClient::new(create_app(Some(output.clone())).await);
+
+        let res = client
+            .get("/session/123/shuffle?label=raffle&n=10")
+            .send()
+            .await;
+        let derived: DerivedRandoms = res.json().await;
+
+        assert_eq!(derived.label, "raffle");
+        assert_eq!(derived.values, derive_shuffle(&output, "raffle", 10));
+
+        let res = client
+            .get(&format!(
+                "/session/123/shuffle?label=raffle&n={}",
+                MAX_DERIVED_ITEMS + 1
+            ))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_get_session_sample() {
+        let output = vec![7_u8; 32];
+        let client = TestClient::new(create_app(Some(output.clone())).await);
+
+        let res = client
+            .get("/session/123/sample?label=raffle&n=10&k=3")
+            .send()
+            .await;
+        let derived: DerivedRandoms = res.json().await;
+
+        assert_eq!(derived.label, "raffle");
+        assert_eq!(
+            derived.values,
+            derive_sample(&output, "raffle", 10, 3).unwrap()
+        );
+
+        let res = client
+            .get("/session/123/sample?label=raffle&n=3&k=10")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res = client
+            .get(&format!(
+                "/session/123/sample?label=raffle&n={}&k={}",
+                MAX_DERIVED_ITEMS + 1,
+                MAX_DERIVED_ITEMS + 1
+            ))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_get_session_weighted() {
+        let output = vec![7_u8; 32];
+        let client = TestClient::new(create_app(Some(output.clone())).await);
+
+        let res = client
+            .get("/session/123/weighted?label=raffle&weights=0,5,0")
+            .send()
+            .await;
+        let derived: DerivedRandoms = res.json().await;
+        assert_eq!(derived.values, vec![1]);
+
+        let res = client
+            .get("/session/123/weighted?label=raffle&weights=1,x")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_get_session_stream() {
+        let output = vec![7_u8; 32];
+        let client = TestClient::new(create_app(Some(output.clone())).await);
+
+        let res = client
+            .get("/session/123/stream?label=raffle&offset=100&len=16")
+            .send()
+            .await;
+        let derived: DerivedStream = res.json().await;
+
+        assert_eq!(derived.offset, 100);
+        assert_eq!(derived.bytes, derive_stream(&output, "raffle", 100, 16));
+    }
+
+    #[tokio::test]
+    async fn test_get_session_range_not_finalized() {
+        let client = TestClient::new(create_app(None).await);
+
+        let res = client
+            .get("/session/123/range?label=raffle&lo=10&hi=20")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+    }
+}
@@ -1,36 +1,194 @@
+mod audit;
+mod auth;
 mod commitment;
 mod cors;
+mod derivation;
+mod proof;
 
+use audit::{get_audit_records, get_requester_stats};
 use axum::{
+    middleware,
     routing::{get, post},
     Router,
 };
 use commitment::{
-    co_commit_to_random, commit_to_random, get_node_address, get_nodes, reveal_random,
+    co_commit_to_random, commit_to_random, get_node_address, get_nodes, release_co_commitment,
+    reveal_random,
 };
+use derivation::{
+    get_session_range, get_session_sample, get_session_shuffle, get_session_stream,
+    get_session_weighted,
+};
+use proof::{finalize_session_proof, get_session_proof};
 use std::sync::Arc;
 use tracing::{event, Level};
 
 use crate::{
     cache::state::AppState,
     utils::peers::{
-        get_co_commit_to_random_endpoint, get_commit_to_random_endpoint, get_nodes_endpoint,
-        get_reveal_random_endpoint,
+        get_audit_endpoint, get_audit_requesters_endpoint, get_co_commit_to_random_endpoint,
+        get_commit_to_random_endpoint, get_nodes_endpoint, get_release_co_commitment_endpoint,
+        get_reveal_random_endpoint, get_session_proof_endpoint, get_session_range_endpoint,
+        get_session_sample_endpoint, get_session_shuffle_endpoint, get_session_stream_endpoint,
+        get_session_weighted_endpoint,
     },
 };
 
+/// Routes of the node on a single listener, the client routes and, over mutual TLS, the peer routes
+/// together.
+///
+/// Without mutual TLS nothing tells peers from clients, so the peer routes are left out and have to
+/// be served on the internal peer listener.
 pub fn create_routes(state: AppState) -> Router {
     event!(Level::DEBUG, "routes::mod::create_routes");
 
+    let state = Arc::new(state);
+    if !state.config.tls_enabled() {
+        return create_public_routes(state);
+    }
+    create_public_routes(state.clone()).merge(create_peer_routes(state))
+}
+
+/// Routes called by clients, on the public listener.
+pub fn create_public_routes(state: Arc<AppState>) -> Router {
+    event!(Level::DEBUG, "routes::mod::create_public_routes");
+
     Router::new()
-        .layer(cors::get_cors())
         .route(&get_commit_to_random_endpoint(), post(commit_to_random))
+        .route(&get_reveal_random_endpoint(), post(reveal_random))
+        .route(&get_nodes_endpoint(), get(get_nodes))
+        .route("/node/:node_id", get(get_node_address))
+        .route(
+            &get_session_proof_endpoint(),
+            get(get_session_proof).post(finalize_session_proof),
+        )
+        .route(&get_session_range_endpoint(), get(get_session_range))
+        .route(&get_session_shuffle_endpoint(), get(get_session_shuffle))
+        .route(&get_session_sample_endpoint(), get(get_session_sample))
+        .route(&get_session_weighted_endpoint(), get(get_session_weighted))
+        .route(&get_session_stream_endpoint(), get(get_session_stream))
+        .layer(cors::get_cors())
+        .with_state(state)
+}
+
+/// Routes called by the other nodes and the operators only, on the internal peer listener if
+/// configured.
+///
+/// The audit log names the requesters of every session, so it is kept off the public listener.
+/// Peers aren't browsers, so the routes are left out of CORS.
+pub fn create_peer_routes(state: Arc<AppState>) -> Router {
+    event!(Level::DEBUG, "routes::mod::create_peer_routes");
+
+    Router::new()
         .route(
             &get_co_commit_to_random_endpoint(),
             post(co_commit_to_random),
         )
-        .route(&get_reveal_random_endpoint(), post(reveal_random))
-        .route(&get_nodes_endpoint(), get(get_nodes))
-        .route("/node/:node_id", get(get_node_address))
-        .with_state(Arc::new(state))
+        .route(
+            &get_release_co_commitment_endpoint(),
+            post(release_co_commitment),
+        )
+        .route(&get_audit_endpoint(), get(get_audit_records))
+        .route(&get_audit_requesters_endpoint(), get(get_requester_stats))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_peer,
+        ))
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use axum_test_helper::TestClient;
+
+    use super::*;
+    use crate::{
+        cache::state::create_state,
+        utils::{
+            config::{NodeConfig, StoreBackend},
+            errors::ConfigError,
+        },
+    };
+
+    #[tokio::test]
+    async fn test_public_and_peer_routes() {
+        let state = Arc::new(create_state(NodeConfig::default()).unwrap());
+
+        let public = TestClient::new(create_public_routes(state.clone()));
+        let res = public
+            .post(&get_co_commit_to_random_endpoint())
+            .json(&"")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = public.get(&get_nodes_endpoint()).send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = public.get(&get_audit_endpoint()).send().await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let peer = TestClient::new(create_peer_routes(state));
+        let res = peer.get(&get_nodes_endpoint()).send().await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let res = peer.get(&get_audit_requesters_endpoint()).send().await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = peer
+            .post(&get_co_commit_to_random_endpoint())
+            .json(&"")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_single_listener_without_tls() {
+        let client = TestClient::new(create_routes(create_state(NodeConfig::default()).unwrap()));
+        let res = client
+            .post(&get_co_commit_to_random_endpoint())
+            .json(&"")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_peer_routes_require_peer_certificate() {
+        // the certificate files are only loaded by the listener and the peer client
+        let mut state = create_state(NodeConfig::default()).unwrap();
+        state.config = Arc::new(NodeConfig {
+            tls_ca_file: Some("ca.pem".into()),
+            tls_cert_file: Some("node-1.pem".into()),
+            tls_key_file: Some("node-1.key".into()),
+            ..NodeConfig::default()
+        });
+
+        let client = TestClient::new(create_peer_routes(Arc::new(state)));
+        let res = client
+            .post(&get_co_commit_to_random_endpoint())
+            .json(&"")
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = client.get(&get_audit_endpoint()).send().await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_create_state_errors() {
+        // startup errors are returned instead of panicking
+        let res = create_state(NodeConfig {
+            session_store: StoreBackend::Sled,
+            ..NodeConfig::default()
+        });
+        assert!(matches!(res, Err(ConfigError::Store(_))));
+        let res = create_state(NodeConfig {
+            audit_log_path: Some(
+                std::env::temp_dir()
+                    .join(uuid::Uuid::new_v4().to_string())
+                    .join("audit.log"),
+            ),
+            ..NodeConfig::default()
+        });
+        assert!(matches!(res, Err(ConfigError::AuditLog(_))));
+    }
 }
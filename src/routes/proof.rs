@@ -1,9 +1,492 @@
-use axum::http::StatusCode;
-use moka::future::Cache;
+use std::sync::Arc;
+
+use axum::{
+    extract::{rejection::JsonRejection, Path, State},
+    http::StatusCode,
+    Json,
+};
+use futures::future::join_all;
+use tracing::{event, Level};
 use uuid::Uuid;
 
-use crate::utils::random::generate_random;
+use crate::{
+    cache::{
+        state::{AppState, ProofRequest, RevealRequest, RevealedRandom, Role, SessionProof},
+        store::ProofUpdate,
+    },
+    routes::commitment::reveal_commitment,
+    utils::{
+        extraction::{extract_random, OUTPUT_LENGTH},
+        identity::reveal_message,
+        peers::{
+            get_peer_address, get_reveal_random_endpoint, send_reveal_request,
+            verify_peer_signature,
+        },
+        transcript::verify_transcript,
+    },
+};
+
+// reveals the opening of a single participant, locally for the dealer and over http for peers
+async fn reveal_opening(
+    node_id: u16,
+    request: RevealRequest,
+    state: &AppState,
+) -> Option<RevealedRandom> {
+    event!(Level::DEBUG, "routes::proof::reveal_opening {}", node_id);
+
+    let commitment_id = request.commitment_id;
+    if node_id == state.config.node_id {
+        let value = reveal_commitment(Role::Dealer, &request, state)
+            .await
+            .ok()?;
+        let commitment = value.commitment.to_bytes();
+        let opening = value.opening.to_bytes();
+        return Some(RevealedRandom {
+            node_id,
+            signature: state.sign(&reveal_message(
+                node_id,
+                commitment_id,
+                Role::Dealer,
+                &commitment,
+                &opening,
+            )),
+            commitment,
+            opening,
+        });
+    }
+
+    let address = format!(
+        "{}{}?role={}",
+        get_peer_address(&state.config, node_id)?,
+        get_reveal_random_endpoint(),
+        Role::CoCommitter
+    );
+    match send_reveal_request(&address, request, Some(state.peer_client.clone())).await {
+        // the response has to be the reveal of the node and the session it was requested for
+        Ok(response) if response.node_id != node_id || response.commitment_id != commitment_id => {
+            event!(
+                Level::ERROR,
+                "routes::proof::reveal_opening::mismatched reveal of node {}",
+                node_id
+            );
+            None
+        }
+        // the reveal has to be signed by the node it was requested from
+        Ok(response)
+            if !verify_peer_signature(
+                &state.config,
+                node_id,
+                &reveal_message(
+                    node_id,
+                    commitment_id,
+                    Role::CoCommitter,
+                    &response.commitment,
+                    &response.opening,
+                ),
+                &response.signature,
+            ) =>
+        {
+            event!(
+                Level::ERROR,
+                "routes::proof::reveal_opening::invalid signature of node {}",
+                node_id
+            );
+            None
+        }
+        Ok(response) => Some(RevealedRandom {
+            node_id,
+            commitment: response.commitment,
+            opening: response.opening,
+            signature: response.signature,
+        }),
+        Err(err) => {
+            event!(
+                Level::ERROR,
+                "routes::proof::reveal_opening::error {:?}",
+                err.without_url()
+            );
+            None
+        }
+    }
+}
+
+// verifies the transcript and sets the final random and output, once every participant has revealed
+fn finalize_proof(proof: &mut SessionProof) {
+    if proof.openings.len() != proof.node_ids.len() {
+        return;
+    }
+
+    match verify_transcript(proof) {
+        Ok(opening) => {
+            proof.random = Some(opening.value);
+            proof.output = Some(extract_random(
+                &opening,
+                proof.commitment_id,
+                &proof.node_ids,
+                OUTPUT_LENGTH,
+            ));
+        }
+        Err(err) => {
+            event!(
+                Level::ERROR,
+                "routes::proof::finalize_proof::error {} {}",
+                proof.commitment_id,
+                err
+            );
+        }
+    }
+}
+
+// merges revealed openings into the session proof, so that openings collected by concurrent requests are kept
+pub(crate) async fn record_openings(
+    commitment_id: u128,
+    revealed: Vec<RevealedRandom>,
+    state: &AppState,
+) -> Option<SessionProof> {
+    event!(Level::DEBUG, "routes::proof::record_openings");
+
+    let update: ProofUpdate = Arc::new(move |proof: &mut SessionProof| {
+        for opening in &revealed {
+            if !proof.openings.iter().any(|o| o.node_id == opening.node_id) {
+                proof.openings.push(opening.clone());
+            }
+        }
+        if proof.random.is_none() {
+            finalize_proof(proof);
+        }
+    });
+    state
+        .cache
+        .update_proof(Uuid::from_u128(commitment_id), update)
+        .await
+}
+
+// returns the output of a finalized session
+pub(crate) async fn get_session_output(
+    commitment_id: u128,
+    state: &AppState,
+) -> Result<Vec<u8>, StatusCode> {
+    let proof = state
+        .cache
+        .get_proof(Uuid::from_u128(commitment_id))
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // the session isn't finalized before all the openings are revealed
+    proof.output.ok_or(StatusCode::CONFLICT)
+}
+
+// returns the stored transcript of the draw, without revealing any opening
+pub async fn get_session_proof(
+    State(state): State<Arc<AppState>>,
+    Path(commitment_id): Path<u128>,
+) -> Result<Json<SessionProof>, StatusCode> {
+    event!(Level::DEBUG, "routes::proof::get_session_proof");
+
+    let proof = state
+        .cache
+        .get_proof(Uuid::from_u128(commitment_id))
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(proof))
+}
+
+// collects the openings of the participants that haven't revealed yet and finalizes the transcript,
+// only the dealer of the session holds its transcript
+pub async fn finalize_session_proof(
+    State(state): State<Arc<AppState>>,
+    Path(commitment_id): Path<u128>,
+    request: Result<Json<ProofRequest>, JsonRejection>,
+) -> Result<Json<SessionProof>, StatusCode> {
+    event!(Level::DEBUG, "routes::proof::finalize_session_proof");
+
+    let key = Uuid::from_u128(commitment_id);
+    let proof = state
+        .cache
+        .get_proof(key)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if proof.random.is_some() {
+        return Ok(Json(proof));
+    }
+
+    // the secret is kept out of the url, where it would end up in access logs
+    let auth_proof = match request {
+        Ok(Json(request)) => request.auth_proof,
+        Err(JsonRejection::MissingJsonContentType(_)) => Vec::new(),
+        Err(rejection) => return Err(rejection.status()),
+    };
+    let pending = proof
+        .node_ids
+        .iter()
+        .filter(|node_id| !proof.openings.iter().any(|o| o.node_id == **node_id))
+        .map(|node_id| {
+            let request = RevealRequest {
+                commitment_id,
+                auth_proof: auth_proof.clone(),
+                // the opening has to open the commitment of the node in the transcript
+                commitment: proof
+                    .commitments
+                    .iter()
+                    .find(|commitment| commitment.node_id == *node_id)
+                    .map(|commitment| commitment.commitment.clone()),
+            };
+            reveal_opening(*node_id, request, &state)
+        });
+    let revealed: Vec<RevealedRandom> = join_all(pending).await.into_iter().flatten().collect();
+
+    let proof = record_openings(commitment_id, revealed, &state)
+        .await
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(proof))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        routing::{get, post},
+        Router,
+    };
+    use axum_test_helper::TestClient;
+
+    use crate::{
+        cache::state::{create_state, CommitmentForRandom, CommittedRandomData},
+        routes::commitment::reveal_random,
+        utils::{
+            commitment::{Commitment, OpeningValue},
+            config::{NodeConfig, RandomMode, StoreBackend},
+            identity::auth_hash,
+            sealing::StoreKeyring,
+        },
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_session_proof() {
+        let random = 123124;
+        let (commitment, opening) = Commitment::new(random);
+        let commitment_id = 456_u128;
+        let key = Uuid::from_u128(commitment_id);
+        let dealer_commitment =
+            CommitmentForRandom::for_test(5, commitment_id, &commitment, &opening);
+
+        let state = create_state(NodeConfig {
+            node_id: 5,
+            num_nodes: 5,
+            ..NodeConfig::default()
+        })
+        .unwrap();
+        state
+            .cache
+            .insert(
+                (key, Role::Dealer),
+                CommittedRandomData {
+                    commitment: commitment.clone(),
+                    opening,
+                    co_commitment: None,
+                    reveal_deadline: None,
+                    requester: "client:127.0.0.1".to_string(),
+                    auth_hash: Some(auth_hash(b"secret")),
+                },
+            )
+            .await
+            .unwrap();
+        state
+            .cache
+            .insert_proof(SessionProof {
+                commitment_id,
+                dealer_id: 5,
+                node_ids: vec![5],
+                commitment: commitment.to_bytes(),
+                commitments: vec![dealer_commitment],
+                openings: Vec::new(),
+                random: None,
+                output: None,
+                random_mode: RandomMode::Integer,
+            })
+            .await
+            .unwrap();
+
+        let shared_state = Arc::new(state);
+        let app = Router::new()
+            .route(
+                "/session/:commitment_id/proof",
+                get(get_session_proof).post(finalize_session_proof),
+            )
+            .with_state(shared_state.clone());
+        let client = TestClient::new(app);
+
+        // reading the transcript doesn't reveal anything
+        let res = client
+            .get(&format!("/session/{}/proof", commitment_id))
+            .send()
+            .await;
+        let proof: SessionProof = res.json().await;
+        assert!(proof.random.is_none());
+        assert!(proof.openings.is_empty());
+
+        // the openings of the session are only revealed with its auth proof
+        let res = client
+            .post(&format!("/session/{}/proof", commitment_id))
+            .send()
+            .await;
+        let proof: SessionProof = res.json().await;
+        assert!(proof.random.is_none());
+        assert!(proof.openings.is_empty());
+
+        let res = client
+            .post(&format!("/session/{}/proof", commitment_id))
+            .json(&ProofRequest {
+                auth_proof: b"guess".to_vec(),
+            })
+            .send()
+            .await;
+        let proof: SessionProof = res.json().await;
+        assert!(proof.openings.is_empty());
+
+        let res = client
+            .post(&format!("/session/{}/proof", commitment_id))
+            .json(&ProofRequest {
+                auth_proof: b"secret".to_vec(),
+            })
+            .send()
+            .await;
+        let proof: SessionProof = res.json().await;
+
+        assert_eq!(proof.random, Some(OpeningValue::Integer(random)));
+        assert_eq!(proof.openings.len(), 1);
+        assert_eq!(proof.output.as_ref().map(Vec::len), Some(OUTPUT_LENGTH));
+        assert!(shared_state.cache.contains((key, Role::Dealer)).await); // kept for repeated reveals
+
+        // repeated requests return the same transcript
+        let res = client
+            .get(&format!("/session/{}/proof", commitment_id))
+            .send()
+            .await;
+        let repeated: SessionProof = res.json().await;
+        assert_eq!(repeated.random, Some(OpeningValue::Integer(random)));
+        assert_eq!(repeated.openings[0].opening, proof.openings[0].opening);
+        assert_eq!(repeated.output, proof.output);
+    }
+
+    #[tokio::test]
+    async fn test_finalize_session_proof_after_restart() {
+        let random = 42;
+        let (commitment, opening) = Commitment::new(random);
+        let commitment_id = Uuid::new_v4().as_u128();
+        let key = Uuid::from_u128(commitment_id);
+        let path = std::env::temp_dir().join(format!(
+            "random-pedersen-test-proof-restart-{}",
+            Uuid::new_v4()
+        ));
+        let config = NodeConfig {
+            node_id: 1,
+            num_nodes: 1,
+            session_store: StoreBackend::Sled,
+            session_store_path: Some(path.clone()),
+            store_key: Some(StoreKeyring::generate("test").unwrap().to_string()),
+            ..NodeConfig::default()
+        };
+
+        // the draw is dealt, then the dealer restarts before it is finalized
+        let state = create_state(config.clone()).unwrap();
+        state
+            .cache
+            .insert(
+                (key, Role::Dealer),
+                CommittedRandomData {
+                    commitment: commitment.clone(),
+                    opening: opening.clone(),
+                    co_commitment: None,
+                    reveal_deadline: None,
+                    requester: "client:127.0.0.1".to_string(),
+                    auth_hash: None,
+                },
+            )
+            .await
+            .unwrap();
+        state
+            .cache
+            .insert_proof(SessionProof {
+                commitment_id,
+                dealer_id: 1,
+                node_ids: vec![1],
+                commitment: commitment.to_bytes(),
+                commitments: vec![CommitmentForRandom::for_test(
+                    1,
+                    commitment_id,
+                    &commitment,
+                    &opening,
+                )],
+                openings: Vec::new(),
+                random: None,
+                output: None,
+                random_mode: RandomMode::Integer,
+            })
+            .await
+            .unwrap();
+        drop(state);
+
+        // sled releases the lock of the store once the flusher of the dropped one has stopped
+        let mut state = None;
+        for _ in 0..100 {
+            if let Ok(restarted) = create_state(config.clone()) {
+                state = Some(restarted);
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let app = Router::new()
+            .route(
+                "/session/:commitment_id/proof",
+                get(get_session_proof).post(finalize_session_proof),
+            )
+            .route("/reveal-random", post(reveal_random))
+            .with_state(Arc::new(state.unwrap()));
+        let client = TestClient::new(app);
+
+        // the dealer entry is revealed by default, as before the restart
+        let res = client
+            .post("/reveal-random")
+            .json(&RevealRequest {
+                commitment_id,
+                ..RevealRequest::default()
+            })
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = client
+            .post(&format!("/session/{}/proof", commitment_id))
+            .send()
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let proof: SessionProof = res.json().await;
+        assert_eq!(proof.random, Some(OpeningValue::Integer(random)));
+        assert_eq!(proof.openings.len(), 1);
+
+        // the finalized transcript is persisted too
+        let res = client
+            .get(&format!("/session/{}/proof", commitment_id))
+            .send()
+            .await;
+        let persisted: SessionProof = res.json().await;
+        assert_eq!(persisted.output, proof.output);
+
+        drop(client);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_session_proof_not_found() {
+        let app = Router::new()
+            .route("/session/:commitment_id/proof", get(get_session_proof))
+            .with_state(Arc::new(create_state(NodeConfig::default()).unwrap()));
 
-pub async fn get_committed_random() -> Result<Vec<u8>, StatusCode> {
-    generate_random(32).map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)
-}
\ No newline at end of file
+        let res = TestClient::new(app).get("/session/789/proof").send().await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
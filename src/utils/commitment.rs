@@ -1,19 +1,58 @@
-use bulletproofs::PedersenGens;
+use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
 use byteorder::{ByteOrder, LittleEndian};
 use curve25519_dalek_ng::{
     ristretto::{CompressedRistretto, RistrettoPoint},
     scalar::Scalar,
 };
+use merlin::Transcript;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 use std::ops;
+use zeroize::Zeroize;
 
-use super::random::generate_random;
+use super::{
+    config::RandomMode,
+    errors::{CommitmentError, CommitmentGenerationError, RandomGenerationError},
+    random::{generate_random, generate_random_scalar},
+};
 
 const RANDOM_LENGTH: usize = 32;
 
+/// Bit range of the integer contributions proven by range proofs.
+pub const RANGE_PROOF_BITS: usize = 32;
+
 static PEDERSEN_GENS: Lazy<PedersenGens> = Lazy::new(PedersenGens::default);
+static BULLETPROOF_GENS: Lazy<BulletproofGens> =
+    Lazy::new(|| BulletproofGens::new(RANGE_PROOF_BITS, 1));
+
+// range proofs are bound to the session and the node, so that they can't be replayed
+fn range_proof_transcript(commitment_id: u128, node_id: u16) -> Transcript {
+    let mut transcript = Transcript::new(b"random-pedersen range proof v1");
+    transcript.append_message(b"commitment_id", &commitment_id.to_le_bytes());
+    transcript.append_u64(b"node_id", node_id as u64);
+    transcript
+}
+
+// proofs of knowledge are bound to the session and the node, so that a commitment derived from
+// other nodes' commitments can't be passed off as a fresh one
+fn knowledge_proof_challenge(
+    commitment_id: u128,
+    node_id: u16,
+    commitment: &RistrettoPoint,
+    nonce: &CompressedRistretto,
+) -> Scalar {
+    let mut transcript = Transcript::new(b"random-pedersen knowledge proof v1");
+    transcript.append_message(b"commitment_id", &commitment_id.to_le_bytes());
+    transcript.append_u64(b"node_id", node_id as u64);
+    transcript.append_message(b"commitment", commitment.compress().as_bytes());
+    transcript.append_message(b"nonce", nonce.as_bytes());
+
+    let mut challenge = [0_u8; 64];
+    transcript.challenge_bytes(b"challenge", &mut challenge);
+    Scalar::from_bytes_mod_order_wide(&challenge)
+}
 
-/// Pedersen commitment to an integer value.
+/// Pedersen commitment to an integer or scalar value.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Commitment {
     pub inner: RistrettoPoint,
@@ -24,32 +63,53 @@ impl Commitment {
     pub(crate) const BYTE_LEN: usize = 32;
 
     /// Creates a commitment with a randomly chosen blinding.
+    pub fn try_new(value: u64) -> Result<(Self, Opening), RandomGenerationError> {
+        Self::with_value(OpeningValue::Integer(value))
+    }
+
+    /// Creates a commitment to a scalar value with a randomly chosen blinding.
+    pub fn try_new_scalar(value: Scalar) -> Result<(Self, Opening), RandomGenerationError> {
+        Self::with_value(OpeningValue::Scalar(value))
+    }
+
+    // panicking constructors are kept for the tests only, the handlers use the fallible ones
+    #[cfg(test)]
     pub fn new(value: u64) -> (Self, Opening) {
-        let random = generate_random(RANDOM_LENGTH).unwrap();
+        Self::try_new(value).expect("random generation failed")
+    }
+
+    #[cfg(test)]
+    pub fn new_scalar(value: Scalar) -> (Self, Opening) {
+        Self::try_new_scalar(value).expect("random generation failed")
+    }
+
+    fn with_value(value: OpeningValue) -> Result<(Self, Opening), RandomGenerationError> {
+        let random = generate_random(RANDOM_LENGTH)?;
         let mut arr = [0; RANDOM_LENGTH];
         arr.copy_from_slice(&random[0..RANDOM_LENGTH]);
         let blinding = Scalar::from_bytes_mod_order(arr);
 
         let opening = Opening::new(value, blinding);
-        (Self::from_opening(&opening), opening)
+        Ok((Self::from_opening(&opening), opening))
     }
 
     /// Creates a commitment from the given opening.
     pub fn from_opening(opening: &Opening) -> Self {
-        let inner = PEDERSEN_GENS.commit(Scalar::from(opening.value), opening.blinding);
+        let inner = PEDERSEN_GENS.commit(opening.value.to_scalar(), opening.blinding);
         Commitment { inner }
     }
 
     /// Attempts to deserialize a commitment from byte slice.
-    pub fn from_slice(slice: &[u8]) -> Option<Self> {
+    pub fn from_slice(slice: &[u8]) -> Result<Self, CommitmentError> {
         if slice.len() != Self::BYTE_LEN {
-            return None;
+            return Err(CommitmentError::InvalidLength);
         }
 
         let compressed_point = CompressedRistretto::from_slice(slice);
         compressed_point
             .decompress()
             .map(|point| Commitment { inner: point })
+            .ok_or(CommitmentError::InvalidPoint)
     }
 
     /// Serializes this commitment to bytes.
@@ -57,6 +117,55 @@ impl Commitment {
         self.inner.compress().as_bytes().to_vec()
     }
 
+    /// Verifies the range proof that this commitment is to an integer of `RANGE_PROOF_BITS` bits.
+    pub fn verify_range(&self, range_proof: &[u8], commitment_id: u128, node_id: u16) -> bool {
+        let proof = match RangeProof::from_bytes(range_proof) {
+            Ok(proof) => proof,
+            Err(_) => return false,
+        };
+
+        proof
+            .verify_single(
+                &BULLETPROOF_GENS,
+                &PEDERSEN_GENS,
+                &mut range_proof_transcript(commitment_id, node_id),
+                &self.inner.compress(),
+                RANGE_PROOF_BITS,
+            )
+            .is_ok()
+    }
+
+    /// Verifies the proof of knowledge of the opening of this commitment.
+    pub fn verify_knowledge(
+        &self,
+        knowledge_proof: &[u8],
+        commitment_id: u128,
+        node_id: u16,
+    ) -> bool {
+        if knowledge_proof.len() != Opening::KNOWLEDGE_PROOF_SIZE {
+            return false;
+        }
+
+        let nonce = CompressedRistretto::from_slice(&knowledge_proof[..32]);
+        let mut value_bytes = [0_u8; 32];
+        value_bytes.copy_from_slice(&knowledge_proof[32..64]);
+        let mut blinding_bytes = [0_u8; 32];
+        blinding_bytes.copy_from_slice(&knowledge_proof[64..]);
+
+        let (nonce_point, value_response, blinding_response) = match (
+            nonce.decompress(),
+            Scalar::from_canonical_bytes(value_bytes),
+            Scalar::from_canonical_bytes(blinding_bytes),
+        ) {
+            (Some(point), Some(value), Some(blinding)) => (point, value, blinding),
+            _ => return false,
+        };
+
+        let challenge = knowledge_proof_challenge(commitment_id, node_id, &self.inner, &nonce);
+        PEDERSEN_GENS.commit(value_response, blinding_response)
+            == nonce_point + challenge * self.inner
+    }
+
     /// Verifies if this commitment corresponds to the provided opening.
     pub fn verify(&self, opening: &Opening) -> bool {
         *self == Self::from_opening(opening)
@@ -73,7 +182,7 @@ impl ops::Add for Commitment {
     }
 }
 
-impl<'a, 'b> ops::Add<&'b Commitment> for &'a Commitment {
+impl<'b> ops::Add<&'b Commitment> for &Commitment {
     type Output = Commitment;
 
     fn add(self, rhs: &'b Commitment) -> Commitment {
@@ -93,7 +202,7 @@ impl ops::Sub for Commitment {
     }
 }
 
-impl<'a, 'b> ops::Sub<&'b Commitment> for &'a Commitment {
+impl<'b> ops::Sub<&'b Commitment> for &Commitment {
     type Output = Commitment;
 
     fn sub(self, rhs: &'b Commitment) -> Commitment {
@@ -103,81 +212,292 @@ impl<'a, 'b> ops::Sub<&'b Commitment> for &'a Commitment {
     }
 }
 
+/// Value committed in an opening.
+///
+/// Integer values are aggregated with overflow checks, while scalar values are aggregated mod the
+/// group order. Integer and scalar values can't be aggregated together, `checked_add` and
+/// `checked_sub` return an error on overflow or mixed modes.
+///
+/// The mode is serialized as an explicit tag, e.g. `{"integer":123}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpeningValue {
+    /// Integer value.
+    Integer(u64),
+    /// Uniformly random scalar value.
+    Scalar(Scalar),
+}
+
+impl OpeningValue {
+    /// Converts the value to a scalar.
+    pub fn to_scalar(self) -> Scalar {
+        match self {
+            OpeningValue::Integer(value) => Scalar::from(value),
+            OpeningValue::Scalar(value) => value,
+        }
+    }
+
+    /// Adds two values, failing if integer values overflow or the modes of the values differ.
+    pub fn checked_add(self, rhs: Self) -> Result<Self, CommitmentError> {
+        match (self, rhs) {
+            (OpeningValue::Integer(lhs), OpeningValue::Integer(rhs)) => lhs
+                .checked_add(rhs)
+                .map(OpeningValue::Integer)
+                .ok_or(CommitmentError::Overflow),
+            (OpeningValue::Scalar(lhs), OpeningValue::Scalar(rhs)) => {
+                Ok(OpeningValue::Scalar(lhs + rhs))
+            }
+            _ => Err(CommitmentError::MixedModes),
+        }
+    }
+
+    /// Subtracts two values, failing if integer values underflow or the modes of the values differ.
+    pub fn checked_sub(self, rhs: Self) -> Result<Self, CommitmentError> {
+        match (self, rhs) {
+            (OpeningValue::Integer(lhs), OpeningValue::Integer(rhs)) => lhs
+                .checked_sub(rhs)
+                .map(OpeningValue::Integer)
+                .ok_or(CommitmentError::Underflow),
+            (OpeningValue::Scalar(lhs), OpeningValue::Scalar(rhs)) => {
+                Ok(OpeningValue::Scalar(lhs - rhs))
+            }
+            _ => Err(CommitmentError::MixedModes),
+        }
+    }
+
+    /// Returns the mode of the value.
+    pub fn mode(self) -> RandomMode {
+        match self {
+            OpeningValue::Integer(_) => RandomMode::Integer,
+            OpeningValue::Scalar(_) => RandomMode::Scalar,
+        }
+    }
+
+    /// Returns the integer value, if this is an integer value.
+    pub fn as_u64(self) -> Option<u64> {
+        match self {
+            OpeningValue::Integer(value) => Some(value),
+            OpeningValue::Scalar(_) => None,
+        }
+    }
+}
+
+impl Zeroize for OpeningValue {
+    fn zeroize(&mut self) {
+        match self {
+            OpeningValue::Integer(value) => value.zeroize(),
+            OpeningValue::Scalar(value) => value.zeroize(),
+        }
+    }
+}
+
+impl From<u64> for OpeningValue {
+    fn from(value: u64) -> Self {
+        OpeningValue::Integer(value)
+    }
+}
+
+impl From<Scalar> for OpeningValue {
+    fn from(value: Scalar) -> Self {
+        OpeningValue::Scalar(value)
+    }
+}
+
+impl PartialEq<u64> for OpeningValue {
+    fn eq(&self, other: &u64) -> bool {
+        self.as_u64() == Some(*other)
+    }
+}
+
+impl PartialEq<OpeningValue> for u64 {
+    fn eq(&self, other: &OpeningValue) -> bool {
+        other == self
+    }
+}
+
+// panicking operators are kept for the tests only, aggregation uses the checked methods
+#[cfg(test)]
+impl ops::Add for OpeningValue {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.checked_add(rhs).expect("integer overflow")
+    }
+}
+
+#[cfg(test)]
+impl ops::Sub for OpeningValue {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.checked_sub(rhs).expect("integer underflow")
+    }
+}
+
+// decodes a canonical scalar from a 32 bytes slice
+fn scalar_from_slice(slice: &[u8]) -> Result<Scalar, CommitmentError> {
+    let mut bytes = [0_u8; 32];
+    bytes.copy_from_slice(slice);
+    Scalar::from_canonical_bytes(bytes).ok_or(CommitmentError::NonCanonicalScalar)
+}
+
 /// Opening for a Pedersen commitment.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Opening {
     /// Committed value.
-    pub value: u64,
+    pub value: OpeningValue,
     blinding: Scalar,
 }
 
 impl Opening {
-    /// Size of a serialized opening.
+    /// Size of a serialized opening to an integer value.
     const BYTE_SIZE: usize = 40;
+    /// Size of a serialized opening to a scalar value.
+    const SCALAR_BYTE_SIZE: usize = 64;
+    /// Size of a serialized proof of knowledge (nonce commitment and two responses).
+    const KNOWLEDGE_PROOF_SIZE: usize = 96;
 
-    pub(crate) fn new(value: u64, blinding: Scalar) -> Self {
-        Opening { value, blinding }
+    pub(crate) fn new(value: impl Into<OpeningValue>, blinding: Scalar) -> Self {
+        Opening {
+            value: value.into(),
+            blinding,
+        }
     }
 
     /// Attempts to deserialize an opening from a slice.
-    pub fn from_slice(slice: &[u8]) -> Option<Self> {
-        if slice.len() != Self::BYTE_SIZE {
-            return None;
-        }
+    pub fn from_slice(slice: &[u8]) -> Result<Self, CommitmentError> {
+        let (value, blinding_bytes) = match slice.len() {
+            Self::BYTE_SIZE => (
+                OpeningValue::Integer(LittleEndian::read_u64(&slice[..8])),
+                &slice[8..],
+            ),
+            Self::SCALAR_BYTE_SIZE => (
+                OpeningValue::Scalar(scalar_from_slice(&slice[..32])?),
+                &slice[32..],
+            ),
+            _ => return Err(CommitmentError::InvalidLength),
+        };
+
+        Ok(Opening {
+            value,
+            blinding: scalar_from_slice(blinding_bytes)?,
+        })
+    }
+
+    /// Adds two openings, failing if integer values overflow or the modes of the values differ.
+    pub fn checked_add(&self, rhs: &Opening) -> Result<Opening, CommitmentError> {
+        Ok(Opening {
+            value: self.value.checked_add(rhs.value)?,
+            blinding: self.blinding + rhs.blinding,
+        })
+    }
 
-        let mut scalar_bytes = [0_u8; 32];
-        scalar_bytes.copy_from_slice(&slice[8..]);
-        Some(Opening {
-            value: LittleEndian::read_u64(&slice[..8]),
-            blinding: Scalar::from_canonical_bytes(scalar_bytes).unwrap(),
+    /// Subtracts two openings, failing if integer values underflow or the modes of the values
+    /// differ.
+    pub fn checked_sub(&self, rhs: &Opening) -> Result<Opening, CommitmentError> {
+        Ok(Opening {
+            value: self.value.checked_sub(rhs.value)?,
+            blinding: self.blinding - rhs.blinding,
         })
     }
 
+    /// Creates a range proof that the committed integer value fits in `RANGE_PROOF_BITS` bits.
+    pub fn prove_range(
+        &self,
+        commitment_id: u128,
+        node_id: u16,
+    ) -> Result<Vec<u8>, CommitmentGenerationError> {
+        let value = self.value.as_u64().ok_or(CommitmentGenerationError)?;
+        if value >> RANGE_PROOF_BITS != 0 {
+            return Err(CommitmentGenerationError);
+        }
+
+        let (proof, _) = RangeProof::prove_single(
+            &BULLETPROOF_GENS,
+            &PEDERSEN_GENS,
+            &mut range_proof_transcript(commitment_id, node_id),
+            value,
+            &self.blinding,
+            RANGE_PROOF_BITS,
+        )?;
+        Ok(proof.to_bytes())
+    }
+
+    /// Creates a non-interactive Schnorr proof of knowledge of this opening, bound to the session
+    /// and the node.
+    pub fn prove_knowledge(
+        &self,
+        commitment_id: u128,
+        node_id: u16,
+    ) -> Result<Vec<u8>, CommitmentGenerationError> {
+        let value_nonce = generate_random_scalar().map_err(|_error| CommitmentGenerationError)?;
+        let blinding_nonce =
+            generate_random_scalar().map_err(|_error| CommitmentGenerationError)?;
+        let nonce = PEDERSEN_GENS.commit(value_nonce, blinding_nonce).compress();
+
+        let commitment = Commitment::from_opening(self);
+        let challenge =
+            knowledge_proof_challenge(commitment_id, node_id, &commitment.inner, &nonce);
+
+        let mut bytes = Vec::with_capacity(Self::KNOWLEDGE_PROOF_SIZE);
+        bytes.extend_from_slice(nonce.as_bytes());
+        bytes.extend_from_slice((value_nonce + challenge * self.value.to_scalar()).as_bytes());
+        bytes.extend_from_slice((blinding_nonce + challenge * self.blinding).as_bytes());
+        Ok(bytes)
+    }
+
     /// Serializes to bytes.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes = [0_u8; Self::BYTE_SIZE];
-        LittleEndian::write_u64(&mut bytes[0..8], self.value);
-        bytes[8..].copy_from_slice(&*self.blinding.as_bytes());
-        bytes.to_vec()
+        let mut bytes = match self.value {
+            OpeningValue::Integer(value) => {
+                let mut bytes = vec![0_u8; Self::BYTE_SIZE];
+                LittleEndian::write_u64(&mut bytes[0..8], value);
+                bytes
+            }
+            OpeningValue::Scalar(value) => {
+                let mut bytes = vec![0_u8; Self::SCALAR_BYTE_SIZE];
+                bytes[0..32].copy_from_slice(value.as_bytes());
+                bytes
+            }
+        };
+        let value_size = bytes.len() - 32;
+        bytes[value_size..].copy_from_slice(self.blinding.as_bytes());
+        bytes
     }
 }
 
+// openings are the only secret of a node, they are wiped when evicted from the session store or dropped
+impl Drop for Opening {
+    fn drop(&mut self) {
+        self.value.zeroize();
+        self.blinding.zeroize();
+    }
+}
+
+#[cfg(test)]
 impl ops::Add for Opening {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self {
-        Opening {
-            value: self.value.checked_add(rhs.value).expect("integer overflow"),
-            blinding: self.blinding + rhs.blinding,
-        }
+        self.checked_add(&rhs).expect("integer overflow")
     }
 }
 
+#[cfg(test)]
 impl ops::Sub for Opening {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self {
-        Opening {
-            value: self
-                .value
-                .checked_sub(rhs.value)
-                .expect("integer underflow"),
-            blinding: self.blinding - rhs.blinding,
-        }
+        self.checked_sub(&rhs).expect("integer underflow")
     }
 }
 
-impl<'a, 'b> ops::Sub<&'b Opening> for &'a Opening {
+#[cfg(test)]
+impl<'b> ops::Sub<&'b Opening> for &Opening {
     type Output = Opening;
 
     fn sub(self, rhs: &'b Opening) -> Opening {
-        Opening {
-            value: self
-                .value
-                .checked_sub(rhs.value)
-                .expect("integer underflow"),
-            blinding: self.blinding - rhs.blinding,
-        }
+        self.checked_sub(rhs).expect("integer underflow")
     }
 }
 
@@ -200,7 +520,7 @@ fn opening_recovery_is_as_expected() {
     let opening_vec = opening.to_bytes();
 
     let opening_bytes: &[u8] = &opening_vec;
-    let open = Opening::from_slice(&opening_bytes).unwrap();
+    let open = Opening::from_slice(opening_bytes).unwrap();
     let commit_from_open = Commitment::from_opening(&open);
 
     assert_eq!(open.value, value);
@@ -214,7 +534,7 @@ fn commitment_recovery_is_as_expected() {
 
     let commitment_vec = commitment.to_bytes();
     let commitment_bytes: &[u8] = &commitment_vec;
-    let commit = Commitment::from_slice(&commitment_bytes).unwrap();
+    let commit = Commitment::from_slice(commitment_bytes).unwrap();
 
     assert_eq!(commitment, commit);
 }
@@ -262,3 +582,131 @@ fn non_unique_mpc_is_as_expected() {
     assert_eq!(value, opening.value);
     assert_eq!(commitment1123, commit);
 }
+
+#[test]
+fn scalar_opening_recovery_is_as_expected() {
+    let value = Scalar::from_bytes_mod_order_wide(&[7_u8; 64]);
+    let (commitment, opening) = Commitment::new_scalar(value);
+
+    let opening_vec = opening.to_bytes();
+    assert_eq!(opening_vec.len(), 64);
+
+    let open = Opening::from_slice(&opening_vec).unwrap();
+    assert_eq!(open.value, OpeningValue::Scalar(value));
+    assert_eq!(commitment, Commitment::from_opening(&open));
+}
+
+#[test]
+fn scalar_mpc_is_as_expected() {
+    let value1 = Scalar::from_bytes_mod_order_wide(&[1_u8; 64]);
+    let value2 = Scalar::from_bytes_mod_order_wide(&[2_u8; 64]);
+    let value3: u64 = 250;
+    let (commitment1, opening1) = Commitment::new_scalar(value1);
+    let (commitment2, opening2) = Commitment::new_scalar(value2);
+    let (_, opening3) = Commitment::new(value3);
+
+    let commitment12 = commitment1 + commitment2;
+
+    // aggregation is done mod the group order
+    let opening = opening1.checked_add(&opening2).unwrap();
+    assert_eq!(opening.value, OpeningValue::Scalar(value1 + value2));
+    assert!(commitment12.verify(&opening));
+
+    // integer and scalar contributions aren't aggregated together
+    assert_eq!(
+        opening.checked_add(&opening3),
+        Err(CommitmentError::MixedModes)
+    );
+    assert_eq!(
+        opening3.checked_sub(&opening),
+        Err(CommitmentError::MixedModes)
+    );
+}
+
+#[test]
+fn opening_value_mode_is_tagged() {
+    let value = OpeningValue::Integer(123);
+    let json = serde_json::to_string(&value).unwrap();
+    assert_eq!(json, r#"{"integer":123}"#);
+    assert_eq!(serde_json::from_str::<OpeningValue>(&json).unwrap(), value);
+
+    let value = OpeningValue::Scalar(Scalar::from(123_u64));
+    let json = serde_json::to_string(&value).unwrap();
+    assert!(json.starts_with(r#"{"scalar":"#));
+    assert_eq!(serde_json::from_str::<OpeningValue>(&json).unwrap(), value);
+}
+
+#[test]
+fn range_proof_is_as_expected() {
+    let (commitment, opening) = Commitment::new(u32::MAX as u64);
+    let range_proof = opening.prove_range(123, 1).unwrap();
+
+    assert!(commitment.verify_range(&range_proof, 123, 1));
+    // bound to the session and the node
+    assert!(!commitment.verify_range(&range_proof, 124, 1));
+    assert!(!commitment.verify_range(&range_proof, 123, 2));
+
+    // values out of range can't be proven
+    let (_, opening) = Commitment::new(u32::MAX as u64 + 1);
+    assert!(opening.prove_range(123, 1).is_err());
+}
+
+#[test]
+fn knowledge_proof_is_as_expected() {
+    let (commitment, opening) = Commitment::new(1234);
+    let knowledge_proof = opening.prove_knowledge(123, 1).unwrap();
+
+    assert!(commitment.verify_knowledge(&knowledge_proof, 123, 1));
+    // bound to the session and the node
+    assert!(!commitment.verify_knowledge(&knowledge_proof, 124, 1));
+    assert!(!commitment.verify_knowledge(&knowledge_proof, 123, 2));
+
+    // a commitment derived from other commitments can't reuse the proof
+    let (other_commitment, _) = Commitment::new(1234);
+    assert!(!(commitment + other_commitment).verify_knowledge(&knowledge_proof, 123, 1));
+}
+
+#[test]
+fn invalid_opening_is_rejected() {
+    let (_, opening) = Commitment::new(1234);
+    let mut opening_bytes = opening.to_bytes();
+
+    assert_eq!(
+        Opening::from_slice(&opening_bytes[1..]),
+        Err(CommitmentError::InvalidLength)
+    );
+
+    // non-canonical blinding
+    opening_bytes[8..].copy_from_slice(&[0xff_u8; 32]);
+    assert_eq!(
+        Opening::from_slice(&opening_bytes),
+        Err(CommitmentError::NonCanonicalScalar)
+    );
+}
+
+#[test]
+fn invalid_commitment_is_rejected() {
+    assert_eq!(
+        Commitment::from_slice(&[0xff_u8; 31]),
+        Err(CommitmentError::InvalidLength)
+    );
+    assert_eq!(
+        Commitment::from_slice(&[0xff_u8; 32]),
+        Err(CommitmentError::InvalidPoint)
+    );
+}
+
+#[test]
+fn opening_overflow_is_rejected() {
+    let (_, opening1) = Commitment::new(u64::MAX);
+    let (_, opening2) = Commitment::new(1);
+
+    assert_eq!(
+        opening1.checked_add(&opening2),
+        Err(CommitmentError::Overflow)
+    );
+    assert_eq!(
+        opening2.checked_sub(&opening1),
+        Err(CommitmentError::Underflow)
+    );
+}
@@ -1,25 +1,887 @@
-use std::env::var;
+use std::{
+    collections::HashSet, ffi::OsString, fs, net::SocketAddr, path::PathBuf, time::Duration,
+};
 
-pub fn get_port() -> String {
-    var("PORT").unwrap_or("7000".to_string())
+use clap::{Parser, ValueEnum};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::store::SessionLimits;
+
+use super::{
+    errors::ConfigError, identity::NodeIdentity, peers::get_peers, sealing::StoreKeyring,
+    tls::create_server_config,
+};
+
+/// Kind of value every node contributes to a draw.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RandomMode {
+    #[default]
+    Integer,
+    Scalar,
+}
+
+impl RandomMode {
+    /// Name of the mode, as serialized.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RandomMode::Integer => "integer",
+            RandomMode::Scalar => "scalar",
+        }
+    }
+}
+
+/// Backend of the session store holding the unrevealed openings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreBackend {
+    Memory,
+    Sled,
+}
+
+/// Entry of the peer table, the node itself included.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PeerConfig {
+    pub id: u16,
+    /// Base URL of the node, e.g. `http://10.0.0.2:7000` or `http://[2001:db8::2]:7000`
+    pub url: String,
+    /// Hex encoded Ed25519 public key of the node, its messages have to be signed when it is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// Base URL of the internal peer listener of the node, co-commitments are sent to `url` without it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer_url: Option<String>,
+}
+
+impl PeerConfig {
+    /// Decoded public key of the node, a key that isn't a hex encoded Ed25519 key is a config error
+    /// rather than a missing key.
+    pub fn decode_public_key(&self) -> Result<Option<Vec<u8>>, ConfigError> {
+        self.public_key
+            .as_ref()
+            .map(|public_key| match hex::decode(public_key) {
+                Ok(bytes) if bytes.len() == 32 => Ok(bytes),
+                _ => Err(ConfigError::InvalidPublicKey(self.id)),
+            })
+            .transpose()
+    }
 }
 
-pub fn get_project() -> String {
-    var("PROJECT").unwrap_or("random_pedersen".to_string())
+// parses a peer flag in the `id=url` format, or `id=url|peer_url` with the url of the peer listener
+fn parse_peer(value: &str) -> Result<PeerConfig, String> {
+    let (id, urls) = value
+        .split_once('=')
+        .ok_or("expected the id=url format".to_string())?;
+    let (url, peer_url) = match urls.split_once('|') {
+        Some((url, peer_url)) => (url, Some(peer_url.trim().to_string())),
+        None => (urls, None),
+    };
+    Ok(PeerConfig {
+        id: id
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid peer id {}", id))?,
+        url: url.trim().to_string(),
+        public_key: None,
+        peer_url,
+    })
 }
 
-pub fn get_service() -> String {
-    var("SERVICE").unwrap_or("node".to_string())
+/// Configuration of a node, loaded once at startup.
+///
+/// Values are read from the TOML config file, if any, then overridden by environment variables
+/// and command-line flags. Without a peer table, the peers are the docker-compose services
+/// `{project}_{service}_{id}` listening on `port`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeConfig {
+    pub port: u16,
+    pub project: String,
+    pub service: String,
+    pub node_id: u16,
+    pub num_nodes: u16,
+    pub mpc_threshold: f64,
+    pub random_mode: RandomMode,
+    pub peers: Vec<PeerConfig>,
+    /// Timeout of a single request to a peer, in milliseconds
+    pub peer_timeout_ms: u64,
+    /// Number of retries of a failed request to a peer
+    pub peer_retries: u32,
+    /// Delay before the first retry, doubled on every further retry, in milliseconds
+    pub peer_backoff_ms: u64,
+    /// Timeout of establishing a connection to a peer, in milliseconds
+    pub peer_connect_timeout_ms: u64,
+    /// Interval of TCP keep-alive probes on peer connections, in seconds
+    pub peer_keepalive_secs: u64,
+    /// Talks HTTP/2 to the peers, multiplexing the requests over a single connection per peer,
+    /// negotiated with ALPN over TLS and with prior knowledge otherwise
+    pub peer_http2: bool,
+    /// Path of the PKCS#8 Ed25519 signing key of the node, messages are unsigned without it
+    pub signing_key_file: Option<PathBuf>,
+    /// Address of the internal listener of the peer endpoints, e.g. `10.0.0.1:7100`, they are
+    /// served on `port` with the client endpoints without it
+    pub peer_listen: Option<SocketAddr>,
+    /// Path of the PEM certificate of the CA issuing the node certificates, peers talk mutual TLS with it
+    pub tls_ca_file: Option<PathBuf>,
+    /// Path of the PEM certificate of the node
+    pub tls_cert_file: Option<PathBuf>,
+    /// Path of the PEM PKCS#8 private key of the node certificate
+    pub tls_key_file: Option<PathBuf>,
+    /// Backend of the session store, unrevealed openings survive restarts with `sled`
+    pub session_store: StoreBackend,
+    /// Path of the directory of the on-disk session store
+    pub session_store_path: Option<PathBuf>,
+    /// Key sealing the openings at rest in the `key_id:hex` format, it takes precedence over the
+    /// keys of `store_key_file`
+    pub store_key: Option<String>,
+    /// Path of the file of the store keys, one `key_id:hex` key per line, the first one seals new
+    /// entries and the others only open entries sealed before a rotation
+    pub store_key_file: Option<PathBuf>,
+    /// Maximum number of unrevealed openings kept by the node
+    pub session_capacity: u64,
+    /// Time an opening is kept after it is stored, in seconds
+    pub session_ttl_secs: u64,
+    /// Time an opening is kept after it is last read, in seconds
+    pub session_tti_secs: u64,
+    /// Maximum reveal window a session can request in place of the TTL and TTI, in seconds
+    pub max_reveal_deadline_secs: u64,
+    /// Time a revealed opening is kept for repeated reveals before it is purged, in seconds. Sessions
+    /// without an `auth_hash` can be revealed again by anyone within it
+    pub reveal_retention_secs: u64,
+    /// Path of the audit log of the openings leaving the session store, only the most recent
+    /// records are kept in memory without it
+    pub audit_log_path: Option<PathBuf>,
 }
 
-pub fn get_node_id() -> String {
-    var("NODE_ID").unwrap_or("1".to_string())
+impl Default for NodeConfig {
+    fn default() -> Self {
+        NodeConfig {
+            port: 7000,
+            project: "random_pedersen".to_string(),
+            service: "node".to_string(),
+            node_id: 1,
+            num_nodes: 2,
+            mpc_threshold: 0.66,
+            random_mode: RandomMode::Integer,
+            peers: Vec::new(),
+            peer_timeout_ms: 5000,
+            peer_retries: 2,
+            peer_backoff_ms: 100,
+            peer_connect_timeout_ms: 1000,
+            peer_keepalive_secs: 60,
+            peer_http2: true,
+            signing_key_file: None,
+            peer_listen: None,
+            tls_ca_file: None,
+            tls_cert_file: None,
+            tls_key_file: None,
+            session_store: StoreBackend::Memory,
+            session_store_path: None,
+            store_key: None,
+            store_key_file: None,
+            session_capacity: 10_000,
+            session_ttl_secs: 30 * 60,
+            session_tti_secs: 5 * 60,
+            max_reveal_deadline_secs: 24 * 60 * 60,
+            reveal_retention_secs: 5 * 60,
+            audit_log_path: None,
+        }
+    }
 }
 
-pub fn get_peer_count() -> String {
-    var("NUM_NODES").unwrap_or("2".to_string())
+// command-line flags, falling back to environment variables
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    /// Path of the TOML config file
+    #[arg(long, env = "CONFIG")]
+    config: Option<PathBuf>,
+    #[arg(long, env = "PORT")]
+    port: Option<u16>,
+    #[arg(long, env = "PROJECT")]
+    project: Option<String>,
+    #[arg(long, env = "SERVICE")]
+    service: Option<String>,
+    #[arg(long, env = "NODE_ID")]
+    node_id: Option<u16>,
+    #[arg(long, env = "NUM_NODES")]
+    num_nodes: Option<u16>,
+    #[arg(long, env = "MPC_THRESHOLD")]
+    mpc_threshold: Option<f64>,
+    #[arg(long, env = "RANDOM_MODE", value_enum)]
+    random_mode: Option<RandomMode>,
+    /// Peer table entry in the `id=url` format, repeated for every node
+    #[arg(long = "peer", env = "PEERS", value_delimiter = ',', value_parser = parse_peer)]
+    peers: Vec<PeerConfig>,
+    #[arg(long, env = "PEER_TIMEOUT_MS")]
+    peer_timeout_ms: Option<u64>,
+    #[arg(long, env = "PEER_RETRIES")]
+    peer_retries: Option<u32>,
+    #[arg(long, env = "PEER_BACKOFF_MS")]
+    peer_backoff_ms: Option<u64>,
+    #[arg(long, env = "PEER_CONNECT_TIMEOUT_MS")]
+    peer_connect_timeout_ms: Option<u64>,
+    #[arg(long, env = "PEER_KEEPALIVE_SECS")]
+    peer_keepalive_secs: Option<u64>,
+    #[arg(long, env = "PEER_HTTP2")]
+    peer_http2: Option<bool>,
+    #[arg(long, env = "SIGNING_KEY_FILE")]
+    signing_key_file: Option<PathBuf>,
+    #[arg(long, env = "PEER_LISTEN")]
+    peer_listen: Option<SocketAddr>,
+    #[arg(long, env = "TLS_CA_FILE")]
+    tls_ca_file: Option<PathBuf>,
+    #[arg(long, env = "TLS_CERT_FILE")]
+    tls_cert_file: Option<PathBuf>,
+    #[arg(long, env = "TLS_KEY_FILE")]
+    tls_key_file: Option<PathBuf>,
+    #[arg(long, env = "SESSION_STORE", value_enum)]
+    session_store: Option<StoreBackend>,
+    #[arg(long, env = "SESSION_STORE_PATH")]
+    session_store_path: Option<PathBuf>,
+    #[arg(long, env = "STORE_KEY", hide_env_values = true)]
+    store_key: Option<String>,
+    #[arg(long, env = "STORE_KEY_FILE")]
+    store_key_file: Option<PathBuf>,
+    #[arg(long, env = "SESSION_CAPACITY")]
+    session_capacity: Option<u64>,
+    #[arg(long, env = "SESSION_TTL_SECS")]
+    session_ttl_secs: Option<u64>,
+    #[arg(long, env = "SESSION_TTI_SECS")]
+    session_tti_secs: Option<u64>,
+    #[arg(long, env = "MAX_REVEAL_DEADLINE_SECS")]
+    max_reveal_deadline_secs: Option<u64>,
+    #[arg(long, env = "REVEAL_RETENTION_SECS")]
+    reveal_retention_secs: Option<u64>,
+    #[arg(long, env = "AUDIT_LOG_PATH")]
+    audit_log_path: Option<PathBuf>,
 }
 
-pub fn get_mpc_threshold() -> String {
-    var("MPC_THRESHOLD").unwrap_or("0.66".to_string())
+impl NodeConfig {
+    /// Loads and validates the configuration from the process arguments and environment.
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::load_from(std::env::args_os())
+    }
+
+    /// Loads and validates the configuration from the given arguments and the environment.
+    pub fn load_from<I, T>(args: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let args =
+            Args::try_parse_from(args).map_err(|err| ConfigError::Arguments(err.to_string()))?;
+
+        let mut config = match &args.config {
+            Some(path) => {
+                let content =
+                    fs::read_to_string(path).map_err(|err| ConfigError::File(err.to_string()))?;
+                toml::from_str(&content).map_err(|err| ConfigError::Parse(err.to_string()))?
+            }
+            None => NodeConfig::default(),
+        };
+
+        if let Some(port) = args.port {
+            config.port = port;
+        }
+        if let Some(project) = args.project {
+            config.project = project;
+        }
+        if let Some(service) = args.service {
+            config.service = service;
+        }
+        if let Some(node_id) = args.node_id {
+            config.node_id = node_id;
+        }
+        if let Some(num_nodes) = args.num_nodes {
+            config.num_nodes = num_nodes;
+        }
+        if let Some(mpc_threshold) = args.mpc_threshold {
+            config.mpc_threshold = mpc_threshold;
+        }
+        if let Some(random_mode) = args.random_mode {
+            config.random_mode = random_mode;
+        }
+        if !args.peers.is_empty() {
+            config.peers = args.peers;
+        }
+        if let Some(peer_timeout_ms) = args.peer_timeout_ms {
+            config.peer_timeout_ms = peer_timeout_ms;
+        }
+        if let Some(peer_retries) = args.peer_retries {
+            config.peer_retries = peer_retries;
+        }
+        if let Some(peer_backoff_ms) = args.peer_backoff_ms {
+            config.peer_backoff_ms = peer_backoff_ms;
+        }
+        if let Some(peer_connect_timeout_ms) = args.peer_connect_timeout_ms {
+            config.peer_connect_timeout_ms = peer_connect_timeout_ms;
+        }
+        if let Some(peer_keepalive_secs) = args.peer_keepalive_secs {
+            config.peer_keepalive_secs = peer_keepalive_secs;
+        }
+        if let Some(peer_http2) = args.peer_http2 {
+            config.peer_http2 = peer_http2;
+        }
+        if let Some(signing_key_file) = args.signing_key_file {
+            config.signing_key_file = Some(signing_key_file);
+        }
+        if let Some(peer_listen) = args.peer_listen {
+            config.peer_listen = Some(peer_listen);
+        }
+        if let Some(tls_ca_file) = args.tls_ca_file {
+            config.tls_ca_file = Some(tls_ca_file);
+        }
+        if let Some(tls_cert_file) = args.tls_cert_file {
+            config.tls_cert_file = Some(tls_cert_file);
+        }
+        if let Some(tls_key_file) = args.tls_key_file {
+            config.tls_key_file = Some(tls_key_file);
+        }
+        if let Some(session_store) = args.session_store {
+            config.session_store = session_store;
+        }
+        if let Some(session_store_path) = args.session_store_path {
+            config.session_store_path = Some(session_store_path);
+        }
+        if let Some(store_key) = args.store_key {
+            config.store_key = Some(store_key);
+        }
+        if let Some(store_key_file) = args.store_key_file {
+            config.store_key_file = Some(store_key_file);
+        }
+        if let Some(session_capacity) = args.session_capacity {
+            config.session_capacity = session_capacity;
+        }
+        if let Some(session_ttl_secs) = args.session_ttl_secs {
+            config.session_ttl_secs = session_ttl_secs;
+        }
+        if let Some(session_tti_secs) = args.session_tti_secs {
+            config.session_tti_secs = session_tti_secs;
+        }
+        if let Some(max_reveal_deadline_secs) = args.max_reveal_deadline_secs {
+            config.max_reveal_deadline_secs = max_reveal_deadline_secs;
+        }
+        if let Some(reveal_retention_secs) = args.reveal_retention_secs {
+            config.reveal_retention_secs = reveal_retention_secs;
+        }
+        if let Some(audit_log_path) = args.audit_log_path {
+            config.audit_log_path = Some(audit_log_path);
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the configuration is consistent, so that misconfigurations fail at startup.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.port == 0 {
+            return Err(ConfigError::InvalidPort);
+        }
+        if self.num_nodes == 0 {
+            return Err(ConfigError::InvalidNodeCount);
+        }
+        if !self.peers.is_empty() && self.peers.len() != self.num_nodes as usize {
+            return Err(ConfigError::PeerCountMismatch(
+                self.peers.len(),
+                self.num_nodes,
+            ));
+        }
+        if !(self.mpc_threshold > 0.0 && self.mpc_threshold <= 1.0) {
+            return Err(ConfigError::InvalidThreshold(self.mpc_threshold));
+        }
+        if self.peer_timeout_ms == 0 || self.peer_connect_timeout_ms == 0 {
+            return Err(ConfigError::InvalidTimeout);
+        }
+        if self
+            .peer_listen
+            .is_some_and(|peer_listen| peer_listen.port() == self.port || peer_listen.port() == 0)
+        {
+            return Err(ConfigError::InvalidPeerListen);
+        }
+
+        if self.session_capacity == 0
+            || self.session_ttl_secs == 0
+            || self.session_tti_secs == 0
+            || self.max_reveal_deadline_secs == 0
+        {
+            return Err(ConfigError::InvalidSessionLimits);
+        }
+        if self.session_store == StoreBackend::Sled && self.session_store_path.is_none() {
+            return Err(ConfigError::MissingStorePath);
+        }
+        // openings are never written to disk in the clear
+        if self.store_keyring()?.is_none() && self.session_store == StoreBackend::Sled {
+            return Err(ConfigError::MissingStoreKey);
+        }
+
+        let peers = get_peers(self);
+        if !peers.iter().any(|peer| peer.id == self.node_id) {
+            return Err(ConfigError::InvalidNodeId(self.node_id));
+        }
+
+        // over mutual TLS, peers are only reachable with https
+        let schemes: &[&str] = if self.tls_enabled() {
+            &["https"]
+        } else {
+            &["http", "https"]
+        };
+        let parse_url = |url: &String| match Url::parse(url) {
+            Ok(parsed) if schemes.contains(&parsed.scheme()) && parsed.host().is_some() => {
+                Ok(parsed)
+            }
+            _ => Err(ConfigError::InvalidPeerUrl(url.clone())),
+        };
+
+        let mut ids = HashSet::new();
+        let mut urls = HashSet::new();
+        for peer in &peers {
+            if !ids.insert(peer.id) {
+                return Err(ConfigError::DuplicatePeer(peer.id.to_string()));
+            }
+            for url in std::iter::once(&peer.url).chain(&peer.peer_url) {
+                if !urls.insert(parse_url(url)?) {
+                    return Err(ConfigError::DuplicatePeer(url.clone()));
+                }
+            }
+            peer.decode_public_key()?;
+        }
+
+        // once a node signs its messages, every node has to, or its messages couldn't be told apart
+        // from unsigned ones
+        if peers.iter().any(|peer| peer.public_key.is_some()) {
+            if let Some(peer) = peers.iter().find(|peer| peer.public_key.is_none()) {
+                return Err(ConfigError::MissingPublicKey(peer.id));
+            }
+        }
+
+        // peers have to know the internal listener of the node
+        if self.peer_listen.is_some()
+            && peers
+                .iter()
+                .any(|peer| peer.id == self.node_id && peer.peer_url.is_none())
+        {
+            return Err(ConfigError::MissingPeerUrl(self.node_id));
+        }
+
+        // the signing key has to match the public key the peers know the node by
+        let identity = self.identity()?;
+        let own_public_key = peers
+            .iter()
+            .find(|peer| peer.id == self.node_id)
+            .and_then(|peer| peer.public_key.as_ref());
+        if let (Some(identity), Some(public_key)) = (identity, own_public_key) {
+            if hex::encode(identity.public_key()) != public_key.to_lowercase() {
+                return Err(ConfigError::KeyMismatch);
+            }
+        }
+
+        create_server_config(self)?;
+
+        // without mutual TLS, only the internal listener keeps clients away from the peer routes
+        if self.num_nodes > 1 && !self.tls_enabled() && self.peer_listen.is_none() {
+            return Err(ConfigError::UnprotectedPeerRoutes);
+        }
+
+        Ok(())
+    }
+
+    /// Whether the peers talk mutual TLS, the certificate files are checked by `validate`.
+    pub fn tls_enabled(&self) -> bool {
+        self.tls_ca_file.is_some() || self.tls_cert_file.is_some() || self.tls_key_file.is_some()
+    }
+
+    /// Loads the signing key of the node, if configured.
+    pub fn identity(&self) -> Result<Option<NodeIdentity>, ConfigError> {
+        match &self.signing_key_file {
+            Some(path) => {
+                let document = fs::read(path).map_err(|err| ConfigError::File(err.to_string()))?;
+                NodeIdentity::from_pkcs8(&document).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Loads the keys sealing the openings at rest, if configured.
+    pub fn store_keyring(&self) -> Result<Option<StoreKeyring>, ConfigError> {
+        let content = match &self.store_key_file {
+            Some(path) => {
+                fs::read_to_string(path).map_err(|err| ConfigError::File(err.to_string()))?
+            }
+            None => String::new(),
+        };
+        // blank lines and comments of the key file are skipped
+        let file_keys = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        StoreKeyring::from_entries(self.store_key.as_deref().into_iter().chain(file_keys))
+    }
+
+    /// Number of peer co-commitments required to complete a draw.
+    pub fn quorum(&self) -> usize {
+        (self.mpc_threshold * self.num_nodes as f64).floor() as usize // mpc_threshold share of num_nodes, rounded down
+    }
+
+    pub fn peer_timeout(&self) -> Duration {
+        Duration::from_millis(self.peer_timeout_ms)
+    }
+
+    pub fn peer_backoff(&self) -> Duration {
+        Duration::from_millis(self.peer_backoff_ms)
+    }
+
+    pub fn peer_connect_timeout(&self) -> Duration {
+        Duration::from_millis(self.peer_connect_timeout_ms)
+    }
+
+    pub fn peer_keepalive(&self) -> Duration {
+        Duration::from_secs(self.peer_keepalive_secs)
+    }
+
+    /// Capacity and expiration of the session store.
+    pub fn session_limits(&self) -> SessionLimits {
+        SessionLimits {
+            max_capacity: self.session_capacity,
+            time_to_live: Duration::from_secs(self.session_ttl_secs),
+            time_to_idle: Duration::from_secs(self.session_tti_secs),
+            reveal_retention: Duration::from_secs(self.reveal_retention_secs),
+            // transcripts are kept for auditing after the openings are purged, and at least as long
+            // as the longest reveal window, since the dealer collects the openings through them
+            proof_retention: Duration::from_secs(24 * 60 * 60).max(self.max_reveal_deadline()),
+        }
+    }
+
+    pub fn max_reveal_deadline(&self) -> Duration {
+        Duration::from_secs(self.max_reveal_deadline_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    // unique path in the temp dir, so that concurrent test runs don't share files
+    fn temp_path(name: &str) -> PathBuf {
+        temp_dir().join(format!("random-pedersen-test-{}-{}", name, Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_load_from_file_and_flags() {
+        let path = temp_path("config.toml");
+        fs::write(
+            &path,
+            "node_id = 2\nnum_nodes = 3\nmpc_threshold = 0.5\nrandom_mode = \"scalar\"\n\
+             peer_listen = \"0.0.0.0:7100\"\n",
+        )
+        .unwrap();
+
+        let config = NodeConfig::load_from([
+            "random-pedersen",
+            "--config",
+            path.to_str().unwrap(),
+            "--node-id",
+            "3",
+        ])
+        .unwrap();
+
+        assert_eq!(config.node_id, 3); // flag overrides the file
+        assert_eq!(config.num_nodes, 3);
+        assert_eq!(config.mpc_threshold, 0.5);
+        assert_eq!(config.random_mode, RandomMode::Scalar);
+        assert_eq!(config.port, 7000);
+        assert_eq!(config.quorum(), 1);
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = NodeConfig {
+            num_nodes: 1,
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Ok(()));
+
+        let config = NodeConfig::default();
+        assert_eq!(config.validate(), Err(ConfigError::UnprotectedPeerRoutes));
+
+        let config = NodeConfig {
+            node_id: 3,
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::InvalidNodeId(3)));
+
+        let config = NodeConfig {
+            mpc_threshold: 1.5,
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::InvalidThreshold(1.5)));
+
+        let config = NodeConfig {
+            num_nodes: 0,
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::InvalidNodeCount));
+
+        let config = NodeConfig {
+            peer_timeout_ms: 0,
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::InvalidTimeout));
+
+        let config = NodeConfig {
+            session_store: StoreBackend::Sled,
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::MissingStorePath));
+
+        let config = NodeConfig {
+            session_tti_secs: 0,
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::InvalidSessionLimits));
+
+        let config = NodeConfig {
+            session_store: StoreBackend::Sled,
+            session_store_path: Some(temp_path("store")),
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::MissingStoreKey));
+
+        let config = NodeConfig {
+            store_key: Some("key:0011".to_string()),
+            ..NodeConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::InvalidStoreKey("key".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_load_peer_table() {
+        let public_key = hex::encode([7_u8; 32]);
+        let path = temp_path("peers.toml");
+        fs::write(
+            &path,
+            format!(
+                r#"
+node_id = 7
+
+peer_listen = "10.0.0.1:7100"
+
+[[peers]]
+id = 7
+url = "http://10.0.0.1:7000"
+peer_url = "http://10.0.0.1:7100"
+public_key = "{}"
+
+[[peers]]
+id = 9
+url = "https://[2001:db8::9]:8443"
+public_key = "{}"
+"#,
+                hex::encode([8_u8; 32]),
+                public_key
+            ),
+        )
+        .unwrap();
+
+        let config =
+            NodeConfig::load_from(["random-pedersen", "--config", path.to_str().unwrap()]).unwrap();
+        assert_eq!(config.num_nodes, 2);
+        assert_eq!(config.peers[1].url, "https://[2001:db8::9]:8443");
+        assert_eq!(config.peers[1].public_key, Some(public_key));
+
+        let config = NodeConfig::load_from([
+            "random-pedersen",
+            "--node-id",
+            "2",
+            "--peer",
+            "1=http://node-a:7000,2=http://node-b:7000|http://10.0.0.2:7100",
+            "--peer-listen",
+            "10.0.0.2:7100",
+        ])
+        .unwrap();
+        assert_eq!(config.peers[1].id, 2);
+        assert_eq!(config.peers[1].url, "http://node-b:7000");
+        assert_eq!(
+            config.peers[1].peer_url.as_deref(),
+            Some("http://10.0.0.2:7100")
+        );
+
+        // the peer table has to list every node of the network
+        let result = NodeConfig::load_from([
+            "random-pedersen",
+            "--num-nodes",
+            "3",
+            "--peer",
+            "1=http://node-a:7000,2=http://node-b:7000",
+        ]);
+        assert_eq!(result.err(), Some(ConfigError::PeerCountMismatch(2, 3)));
+
+        // without mutual TLS, the peer routes of a cluster are only served on a peer listener
+        let result = NodeConfig::load_from([
+            "random-pedersen",
+            "--peer",
+            "1=http://node-a:7000,2=http://node-b:7000",
+        ]);
+        assert_eq!(result.err(), Some(ConfigError::UnprotectedPeerRoutes));
+    }
+
+    #[test]
+    fn test_validate_peer_table() {
+        let peer = |id: u16, url: &str| PeerConfig {
+            id,
+            url: url.to_string(),
+            public_key: None,
+            peer_url: None,
+        };
+
+        let config = NodeConfig {
+            peers: vec![peer(1, "http://node-a:7000"), peer(1, "http://node-b:7000")],
+            ..NodeConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::DuplicatePeer("1".to_string()))
+        );
+
+        let config = NodeConfig {
+            peers: vec![
+                peer(1, "http://node-a:7000"),
+                peer(2, "http://node-a:7000/"),
+            ],
+            ..NodeConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::DuplicatePeer(
+                "http://node-a:7000/".to_string()
+            ))
+        );
+
+        let config = NodeConfig {
+            peers: vec![peer(1, "http://node-a:7000"), peer(2, "node-b:7000")],
+            ..NodeConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::InvalidPeerUrl("node-b:7000".to_string()))
+        );
+
+        let config = NodeConfig {
+            node_id: 3,
+            num_nodes: 2,
+            peers: vec![peer(1, "http://node-a:7000"), peer(2, "http://node-b:7000")],
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::InvalidNodeId(3)));
+    }
+
+    #[test]
+    fn test_validate_peer_listen() {
+        let peer = |id: u16, peer_url: Option<&str>| PeerConfig {
+            id,
+            url: format!("http://node-{}:7000", id),
+            public_key: None,
+            peer_url: peer_url.map(str::to_string),
+        };
+
+        let config = NodeConfig {
+            peer_listen: Some("10.0.0.1:7100".parse().unwrap()),
+            peers: vec![peer(1, Some("http://10.0.0.1:7100")), peer(2, None)],
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Ok(()));
+
+        let config = NodeConfig {
+            peer_listen: Some("10.0.0.1:7000".parse().unwrap()),
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::InvalidPeerListen));
+
+        let config = NodeConfig {
+            peer_listen: Some("10.0.0.1:7100".parse().unwrap()),
+            peers: vec![peer(1, None), peer(2, Some("http://10.0.0.2:7100"))],
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::MissingPeerUrl(1)));
+
+        let config = NodeConfig {
+            peers: vec![peer(1, Some("http://node-2:7000")), peer(2, None)],
+            ..NodeConfig::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::DuplicatePeer("http://node-2:7000".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_validate_signing_key() {
+        let document = NodeIdentity::generate().unwrap();
+        let identity = NodeIdentity::from_pkcs8(&document).unwrap();
+        let path = temp_path("node.key");
+        fs::write(&path, &document).unwrap();
+
+        let peers = |public_key: String| {
+            vec![
+                PeerConfig {
+                    id: 1,
+                    url: "http://node-a:7000".to_string(),
+                    public_key: Some(public_key),
+                    peer_url: Some("http://node-a:7100".to_string()),
+                },
+                PeerConfig {
+                    id: 2,
+                    url: "http://node-b:7000".to_string(),
+                    public_key: Some(hex::encode([7_u8; 32])),
+                    peer_url: None,
+                },
+            ]
+        };
+
+        let config = NodeConfig {
+            peers: peers(hex::encode(identity.public_key())),
+            signing_key_file: Some(path.clone()),
+            peer_listen: Some("0.0.0.0:7100".parse().unwrap()),
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Ok(()));
+
+        let other = NodeIdentity::from_pkcs8(&NodeIdentity::generate().unwrap()).unwrap();
+        let config = NodeConfig {
+            peers: peers(hex::encode(other.public_key())),
+            signing_key_file: Some(path),
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::KeyMismatch));
+
+        let config = NodeConfig {
+            peers: peers("abcd".to_string()),
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::InvalidPublicKey(1)));
+
+        // once a node has a public key, every node needs one
+        let mut peers = peers(hex::encode(identity.public_key()));
+        peers[1].public_key = None;
+        let config = NodeConfig {
+            peers,
+            peer_listen: Some("0.0.0.0:7100".parse().unwrap()),
+            ..NodeConfig::default()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::MissingPublicKey(2)));
+    }
+
+    #[test]
+    fn test_load_from_invalid_flag() {
+        let result = NodeConfig::load_from(["random-pedersen", "--num-nodes", "many"]);
+        assert!(matches!(result, Err(ConfigError::Arguments(_))));
+    }
 }
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use merlin::Transcript;
+use rand_chacha::{
+    rand_core::{RngCore, SeedableRng},
+    ChaCha20Rng,
+};
+
+use super::errors::DerivationError;
+
+// domain separator of the derivations, changing it changes every derived value
+const DERIVATION_DOMAIN: &[u8] = b"random-pedersen derivation v1";
+
+/// Creates a ChaCha20 generator keyed by the session output, the derivation kind and the label.
+///
+/// The same output, kind and label always result in the same stream, so any verifier holding
+/// the session output can reproduce the derived values.
+pub fn derive_rng(output: &[u8], kind: &str, label: &str) -> ChaCha20Rng {
+    let mut transcript = Transcript::new(DERIVATION_DOMAIN);
+    transcript.append_message(b"output", output);
+    transcript.append_message(b"kind", kind.as_bytes());
+    transcript.append_message(b"label", label.as_bytes());
+
+    let mut seed = [0_u8; 32];
+    transcript.challenge_bytes(b"seed", &mut seed);
+    ChaCha20Rng::from_seed(seed)
+}
+
+/// Derives `length` bytes of the session stream for the label, starting at byte `offset`.
+///
+/// Any window of the stream can be reproduced independently, `offset` seeks the ChaCha20
+/// keystream instead of generating the preceding bytes.
+pub fn derive_stream(output: &[u8], label: &str, offset: u64, length: usize) -> Vec<u8> {
+    let mut rng = derive_rng(output, "stream", label);
+    // the keystream is positioned in 32-bit words
+    rng.set_word_pos((offset / 4) as u128);
+
+    let skip = (offset % 4) as usize;
+    let mut bytes = vec![0_u8; skip + length];
+    rng.fill_bytes(&mut bytes);
+    bytes.split_off(skip)
+}
+
+// draws an unbiased integer in [0, span) by rejecting the values of the incomplete last span
+fn below(rng: &mut impl RngCore, span: u64) -> u64 {
+    // 2^64 mod span, values below it would be overrepresented
+    let threshold = span.wrapping_neg() % span;
+    loop {
+        let value = rng.next_u64();
+        if value >= threshold {
+            return value % span;
+        }
+    }
+}
+
+/// Derives an unbiased integer in `[lo, hi)`.
+pub fn derive_range(output: &[u8], label: &str, lo: u64, hi: u64) -> Result<u64, DerivationError> {
+    if lo >= hi {
+        return Err(DerivationError::EmptyRange);
+    }
+
+    let mut rng = derive_rng(output, "range", label);
+    Ok(lo + below(&mut rng, hi - lo))
+}
+
+/// Derives a uniformly random permutation of `0..n` using the Fisher–Yates shuffle.
+pub fn derive_shuffle(output: &[u8], label: &str, n: u64) -> Vec<u64> {
+    let mut rng = derive_rng(output, "shuffle", label);
+    let mut items: Vec<u64> = (0..n).collect();
+    for i in (1..items.len()).rev() {
+        let j = below(&mut rng, i as u64 + 1) as usize;
+        items.swap(i, j);
+    }
+    items
+}
+
+/// Derives `k` distinct items of `0..n`, in random order, without replacement.
+///
+/// Runs a partial Fisher–Yates shuffle over a sparse map, so memory only grows with `k`.
+pub fn derive_sample(
+    output: &[u8],
+    label: &str,
+    n: u64,
+    k: u64,
+) -> Result<Vec<u64>, DerivationError> {
+    if k > n {
+        return Err(DerivationError::SampleTooLarge);
+    }
+
+    let mut rng = derive_rng(output, "sample", label);
+    let mut swapped: HashMap<u64, u64> = HashMap::new();
+    let mut sample = Vec::with_capacity(k as usize);
+    for i in 0..k {
+        let j = i + below(&mut rng, n - i);
+        let item_j = *swapped.get(&j).unwrap_or(&j);
+        let item_i = *swapped.get(&i).unwrap_or(&i);
+        swapped.insert(j, item_i);
+        sample.push(item_j);
+    }
+    Ok(sample)
+}
+
+/// Derives an index of `weights`, chosen with probability proportional to its weight.
+pub fn derive_weighted(
+    output: &[u8],
+    label: &str,
+    weights: &[u64],
+) -> Result<usize, DerivationError> {
+    let total = weights
+        .iter()
+        .try_fold(0_u64, |total, weight| total.checked_add(*weight))
+        .ok_or(DerivationError::WeightOverflow)?;
+    if total == 0 {
+        return Err(DerivationError::EmptyWeights);
+    }
+
+    let mut rng = derive_rng(output, "weighted", label);
+    let mut target = below(&mut rng, total);
+    for (index, weight) in weights.iter().enumerate() {
+        if target < *weight {
+            return Ok(index);
+        }
+        target -= weight;
+    }
+    unreachable!("target is below the sum of the weights")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    const OUTPUT: [u8; 32] = [7_u8; 32];
+
+    #[test]
+    fn test_derive_stream() {
+        let stream = derive_stream(&OUTPUT, "raffle", 0, 64);
+
+        assert_eq!(stream.len(), 64);
+        assert_eq!(derive_stream(&OUTPUT, "raffle", 13, 30), stream[13..43]);
+        assert_eq!(derive_stream(&OUTPUT, "raffle", 32, 32), stream[32..]);
+        assert_ne!(derive_stream(&OUTPUT, "other", 0, 64), stream);
+    }
+
+    #[test]
+    fn test_derive_range() {
+        for index in 0..100 {
+            let value = derive_range(&OUTPUT, &index.to_string(), 10, 20).unwrap();
+            assert!((10..20).contains(&value));
+        }
+
+        assert_eq!(
+            derive_range(&OUTPUT, "raffle", 0, 1_000_000),
+            derive_range(&OUTPUT, "raffle", 0, 1_000_000)
+        );
+        assert_eq!(
+            derive_range(&OUTPUT, "raffle", 5, 5),
+            Err(DerivationError::EmptyRange)
+        );
+    }
+
+    #[test]
+    fn test_derive_shuffle() {
+        let permutation = derive_shuffle(&OUTPUT, "raffle", 100);
+
+        let mut sorted = permutation.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..100).collect::<Vec<u64>>());
+        assert_ne!(permutation, sorted);
+        assert_eq!(permutation, derive_shuffle(&OUTPUT, "raffle", 100));
+        assert_ne!(permutation, derive_shuffle(&OUTPUT, "other", 100));
+    }
+
+    #[test]
+    fn test_derive_sample() {
+        let sample = derive_sample(&OUTPUT, "raffle", 1_000_000_000, 50).unwrap();
+
+        assert_eq!(sample.len(), 50);
+        assert_eq!(sample.iter().collect::<HashSet<_>>().len(), 50);
+        assert!(sample.iter().all(|item| *item < 1_000_000_000));
+
+        let mut all = derive_sample(&OUTPUT, "raffle", 10, 10).unwrap();
+        all.sort_unstable();
+        assert_eq!(all, (0..10).collect::<Vec<u64>>());
+
+        assert_eq!(
+            derive_sample(&OUTPUT, "raffle", 10, 11),
+            Err(DerivationError::SampleTooLarge)
+        );
+    }
+
+    #[test]
+    fn test_derive_weighted() {
+        for index in 0..100 {
+            let choice = derive_weighted(&OUTPUT, &index.to_string(), &[0, 3, 0, 1]).unwrap();
+            assert!(choice == 1 || choice == 3);
+        }
+
+        assert_eq!(
+            derive_weighted(&OUTPUT, "raffle", &[0, 0]),
+            Err(DerivationError::EmptyWeights)
+        );
+        assert_eq!(
+            derive_weighted(&OUTPUT, "raffle", &[u64::MAX, 1]),
+            Err(DerivationError::WeightOverflow)
+        );
+    }
+}
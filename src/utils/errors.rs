@@ -1,11 +1,43 @@
-#[cfg(feature = "std")]
 use std::fmt;
 
 use axum::http::StatusCode;
 use bulletproofs::ProofError;
 
-#[derive(Debug)]
-pub struct CacheError;
+// Custom error type for session store writes to a commitment id that is already taken, and storage failures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheError {
+    Taken,
+    Storage(String),
+}
+
+// Implement From trait sled::Error for CacheError
+impl From<sled::Error> for CacheError {
+    fn from(err: sled::Error) -> Self {
+        CacheError::Storage(err.to_string())
+    }
+}
+
+// Implement To trait StatusCode for CacheError
+impl From<CacheError> for StatusCode {
+    fn from(err: CacheError) -> Self {
+        match err {
+            CacheError::Taken => StatusCode::CONFLICT,
+            CacheError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+// Implement Display trait for CacheError
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Taken => write!(f, "Commitment id is already taken"),
+            CacheError::Storage(err) => write!(f, "Session store error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
 
 // Custom error type for random generation errors
 #[derive(Debug)]
@@ -19,14 +51,13 @@ impl From<ring::error::Unspecified> for RandomGenerationError {
 }
 
 // Implement To trait StatusCode for RandomGenerationError
-impl Into<StatusCode> for RandomGenerationError {
-    fn into(self) -> StatusCode {
+impl From<RandomGenerationError> for StatusCode {
+    fn from(_: RandomGenerationError) -> Self {
         StatusCode::INTERNAL_SERVER_ERROR
     }
 }
 
 // Implement Display trait for RandomGenerationError
-#[cfg(feature = "std")]
 impl fmt::Display for RandomGenerationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "Error generating random bytes")
@@ -45,16 +76,321 @@ impl From<ProofError> for CommitmentGenerationError {
 }
 
 // Implement To trait StatusCode for CommitmentGenerationError
-impl Into<StatusCode> for CommitmentGenerationError {
-    fn into(self) -> StatusCode {
+impl From<CommitmentGenerationError> for StatusCode {
+    fn from(_: CommitmentGenerationError) -> Self {
         StatusCode::INTERNAL_SERVER_ERROR
     }
 }
 
 // Implement Display trait for RandomGenerationError
-#[cfg(feature = "std")]
 impl fmt::Display for CommitmentGenerationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "Error generating random bytes")
     }
 }
+
+// Custom error type for commitment and opening decoding and arithmetic errors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommitmentError {
+    InvalidLength,
+    InvalidPoint,
+    NonCanonicalScalar,
+    Overflow,
+    Underflow,
+    MixedModes,
+}
+
+// Implement To trait StatusCode for CommitmentError
+impl From<CommitmentError> for StatusCode {
+    fn from(_: CommitmentError) -> Self {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+// Implement Display trait for CommitmentError
+impl fmt::Display for CommitmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommitmentError::InvalidLength => write!(f, "Invalid length"),
+            CommitmentError::InvalidPoint => write!(f, "Invalid Ristretto point"),
+            CommitmentError::NonCanonicalScalar => write!(f, "Non-canonical scalar"),
+            CommitmentError::Overflow => write!(f, "Integer overflow"),
+            CommitmentError::Underflow => write!(f, "Integer underflow"),
+            CommitmentError::MixedModes => write!(f, "Integer and scalar values can't be mixed"),
+        }
+    }
+}
+
+impl std::error::Error for CommitmentError {}
+
+// Custom error type for transcript verification errors, naming the node whose data fails
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptError {
+    InvalidAggregatedCommitment,
+    MissingDealer(u16),
+    DuplicateParticipant(u16),
+    MissingOpening(u16),
+    MissingCommitment(u16),
+    UnexpectedOpening(u16),
+    InvalidCommitment(u16),
+    InvalidOpening(u16),
+    CommitmentMismatch(u16),
+    OpeningMismatch(u16),
+    OpeningOverflow(u16),
+    MixedModes(u16),
+    InvalidRangeProof(u16),
+    InvalidKnowledgeProof(u16),
+    InvalidSignature(u16),
+    UnknownNode(u16),
+    MissingPublicKey(u16),
+    AggregatedCommitmentMismatch,
+    AggregatedOpeningMismatch,
+    RandomMismatch,
+    OutputMismatch,
+}
+
+// Implement Display trait for TranscriptError
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptError::InvalidAggregatedCommitment => {
+                write!(f, "Aggregated commitment can't be decoded")
+            }
+            TranscriptError::MissingDealer(node_id) => {
+                write!(f, "Dealer {} is not a participant", node_id)
+            }
+            TranscriptError::DuplicateParticipant(node_id) => {
+                write!(f, "Node {} is listed more than once", node_id)
+            }
+            TranscriptError::MissingOpening(node_id) => {
+                write!(f, "Opening of node {} is missing", node_id)
+            }
+            TranscriptError::MissingCommitment(node_id) => {
+                write!(f, "Commitment of node {} is not recorded", node_id)
+            }
+            TranscriptError::UnexpectedOpening(node_id) => {
+                write!(f, "Opening of node {} is not expected", node_id)
+            }
+            TranscriptError::InvalidCommitment(node_id) => {
+                write!(f, "Commitment of node {} can't be decoded", node_id)
+            }
+            TranscriptError::InvalidOpening(node_id) => {
+                write!(f, "Opening of node {} can't be decoded", node_id)
+            }
+            TranscriptError::CommitmentMismatch(node_id) => {
+                write!(
+                    f,
+                    "Revealed commitment of node {} doesn't match its co-commitment",
+                    node_id
+                )
+            }
+            TranscriptError::OpeningMismatch(node_id) => {
+                write!(
+                    f,
+                    "Opening of node {} doesn't match its commitment",
+                    node_id
+                )
+            }
+            TranscriptError::OpeningOverflow(node_id) => {
+                write!(
+                    f,
+                    "Opening of node {} overflows the aggregated opening",
+                    node_id
+                )
+            }
+            TranscriptError::MixedModes(node_id) => {
+                write!(
+                    f,
+                    "Contribution of node {} isn't in the mode of the session",
+                    node_id
+                )
+            }
+            TranscriptError::InvalidRangeProof(node_id) => {
+                write!(f, "Range proof of node {} is invalid", node_id)
+            }
+            TranscriptError::InvalidKnowledgeProof(node_id) => {
+                write!(f, "Proof of knowledge of node {} is invalid", node_id)
+            }
+            TranscriptError::InvalidSignature(node_id) => {
+                write!(f, "Signature of node {} is invalid", node_id)
+            }
+            TranscriptError::UnknownNode(node_id) => {
+                write!(f, "Node {} is not in the peer table", node_id)
+            }
+            TranscriptError::MissingPublicKey(node_id) => {
+                write!(
+                    f,
+                    "Public key of node {} is missing from the peer table",
+                    node_id
+                )
+            }
+            TranscriptError::AggregatedCommitmentMismatch => {
+                write!(
+                    f,
+                    "Node commitments don't add up to the aggregated commitment"
+                )
+            }
+            TranscriptError::AggregatedOpeningMismatch => {
+                write!(f, "Node openings don't open the aggregated commitment")
+            }
+            TranscriptError::RandomMismatch => {
+                write!(f, "Final random doesn't match the aggregated opening")
+            }
+            TranscriptError::OutputMismatch => {
+                write!(
+                    f,
+                    "Output doesn't match the output extracted from the aggregated opening"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TranscriptError {}
+
+// Custom error type for invalid derivation parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DerivationError {
+    EmptyRange,
+    SampleTooLarge,
+    EmptyWeights,
+    WeightOverflow,
+}
+
+// Implement To trait StatusCode for DerivationError
+impl From<DerivationError> for StatusCode {
+    fn from(_: DerivationError) -> Self {
+        StatusCode::BAD_REQUEST
+    }
+}
+
+// Implement Display trait for DerivationError
+impl fmt::Display for DerivationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DerivationError::EmptyRange => write!(f, "Range is empty"),
+            DerivationError::SampleTooLarge => write!(f, "Sample is larger than the population"),
+            DerivationError::EmptyWeights => write!(f, "Weights are empty or all zero"),
+            DerivationError::WeightOverflow => write!(f, "Sum of the weights overflows"),
+        }
+    }
+}
+
+impl std::error::Error for DerivationError {}
+
+// Custom error type for node configuration errors
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    Arguments(String),
+    File(String),
+    Parse(String),
+    InvalidPort,
+    InvalidNodeCount,
+    PeerCountMismatch(usize, u16),
+    InvalidNodeId(u16),
+    InvalidThreshold(f64),
+    InvalidTimeout,
+    InvalidPeerUrl(String),
+    InvalidPublicKey(u16),
+    MissingPublicKey(u16),
+    InvalidSigningKey,
+    KeyMismatch,
+    DuplicatePeer(String),
+    InvalidPeerListen,
+    UnprotectedPeerRoutes,
+    MissingPeerUrl(u16),
+    IncompleteTls,
+    Tls(String),
+    MissingStorePath,
+    InvalidStoreKey(String),
+    MissingStoreKey,
+    InvalidSessionLimits,
+    AuditLog(String),
+    Store(String),
+}
+
+// Implement Display trait for ConfigError
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Arguments(err) => write!(f, "Invalid arguments: {}", err),
+            ConfigError::File(err) => write!(f, "Error reading config file: {}", err),
+            ConfigError::Parse(err) => write!(f, "Error parsing config file: {}", err),
+            ConfigError::InvalidPort => write!(f, "Port must be greater than 0"),
+            ConfigError::InvalidNodeCount => write!(f, "Number of nodes must be greater than 0"),
+            ConfigError::PeerCountMismatch(peers, num_nodes) => write!(
+                f,
+                "Peer table lists {} nodes while num_nodes is {}",
+                peers, num_nodes
+            ),
+            ConfigError::InvalidNodeId(node_id) => {
+                write!(f, "Node id {} is outside of the node range", node_id)
+            }
+            ConfigError::InvalidThreshold(threshold) => {
+                write!(f, "MPC threshold {} is outside of (0, 1]", threshold)
+            }
+            ConfigError::InvalidTimeout => write!(f, "Peer timeouts must be greater than 0"),
+            ConfigError::InvalidPeerUrl(url) => write!(f, "Invalid peer url {}", url),
+            ConfigError::InvalidPublicKey(node_id) => {
+                write!(f, "Invalid public key of node {}", node_id)
+            }
+            ConfigError::MissingPublicKey(node_id) => {
+                write!(
+                    f,
+                    "Public key of node {} is missing while other nodes have one",
+                    node_id
+                )
+            }
+            ConfigError::InvalidSigningKey => write!(f, "Invalid signing key"),
+            ConfigError::KeyMismatch => {
+                write!(f, "Signing key doesn't match the public key of the node")
+            }
+            ConfigError::DuplicatePeer(peer) => write!(f, "Duplicate peer {}", peer),
+            ConfigError::UnprotectedPeerRoutes => {
+                write!(
+                    f,
+                    "Peer routes need either mutual TLS or a peer listener with more than one node"
+                )
+            }
+            ConfigError::InvalidPeerListen => {
+                write!(
+                    f,
+                    "Peer listener port must be greater than 0 and differ from the port"
+                )
+            }
+            ConfigError::MissingPeerUrl(node_id) => {
+                write!(
+                    f,
+                    "Peer url of node {} is missing for the peer listener",
+                    node_id
+                )
+            }
+            ConfigError::IncompleteTls => {
+                write!(f, "TLS needs the CA, certificate and key files together")
+            }
+            ConfigError::Tls(err) => write!(f, "Invalid TLS config: {}", err),
+            ConfigError::MissingStorePath => {
+                write!(f, "On-disk session store needs a session store path")
+            }
+            ConfigError::InvalidStoreKey(key_id) => {
+                write!(f, "Invalid store key {}", key_id)
+            }
+            ConfigError::InvalidSessionLimits => {
+                write!(
+                    f,
+                    "Session capacity, timeouts and maximum reveal deadline must be greater than 0"
+                )
+            }
+            ConfigError::MissingStoreKey => {
+                write!(
+                    f,
+                    "On-disk session store needs a store key to seal the openings"
+                )
+            }
+            ConfigError::AuditLog(err) => write!(f, "Error opening the audit log: {}", err),
+            ConfigError::Store(err) => write!(f, "Error opening the session store: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
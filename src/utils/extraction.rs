@@ -0,0 +1,75 @@
+use merlin::Transcript;
+
+use super::commitment::Opening;
+
+// domain separator of the extraction, changing it changes every extracted output
+const EXTRACTION_DOMAIN: &[u8] = b"random-pedersen extraction v1";
+
+/// Length of the extracted session output.
+pub const OUTPUT_LENGTH: usize = 32;
+
+/// Extracts uniform output bytes from the aggregated opening of a session.
+///
+/// The opening is absorbed together with the session id and the sorted participant list into a
+/// domain-separated Merlin transcript (STROBE-128 over Keccak-f), which is then squeezed as an
+/// XOF for `length` bytes.
+pub fn extract_random(
+    opening: &Opening,
+    commitment_id: u128,
+    node_ids: &[u16],
+    length: usize,
+) -> Vec<u8> {
+    let mut node_ids = node_ids.to_vec();
+    node_ids.sort_unstable();
+
+    let mut transcript = Transcript::new(EXTRACTION_DOMAIN);
+    transcript.append_message(b"commitment_id", &commitment_id.to_le_bytes());
+    transcript.append_u64(b"node_count", node_ids.len() as u64);
+    for node_id in node_ids {
+        transcript.append_u64(b"node_id", node_id as u64);
+    }
+    transcript.append_message(b"opening", &opening.to_bytes());
+
+    let mut output = vec![0_u8; length];
+    transcript.challenge_bytes(b"output", &mut output);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::commitment::Commitment;
+
+    use super::*;
+
+    #[test]
+    fn test_extract_random_is_deterministic() {
+        let (_, opening) = Commitment::new(1234);
+
+        let output1 = extract_random(&opening, 123, &[1, 2, 3], OUTPUT_LENGTH);
+        let output2 = extract_random(&opening, 123, &[3, 1, 2], OUTPUT_LENGTH);
+
+        assert_eq!(output1.len(), OUTPUT_LENGTH);
+        assert_eq!(output1, output2);
+    }
+
+    #[test]
+    fn test_extract_random_is_bound_to_session() {
+        let (_, opening) = Commitment::new(1234);
+        let output = extract_random(&opening, 123, &[1, 2, 3], OUTPUT_LENGTH);
+
+        assert_ne!(
+            output,
+            extract_random(&opening, 124, &[1, 2, 3], OUTPUT_LENGTH)
+        );
+        assert_ne!(
+            output,
+            extract_random(&opening, 123, &[1, 2], OUTPUT_LENGTH)
+        );
+
+        let (_, other_opening) = Commitment::new(1234);
+        assert_ne!(
+            output,
+            extract_random(&other_opening, 123, &[1, 2, 3], OUTPUT_LENGTH)
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use ring::{
+    digest::{digest, SHA256},
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519},
+};
+
+use crate::{
+    cache::state::{CommitmentForRandom, Role},
+    utils::errors::{ConfigError, RandomGenerationError},
+};
+
+// domain separator of the signed messages, changing it invalidates every signature
+const SIGNATURE_DOMAIN: &[u8] = b"random-pedersen signature v1";
+
+/// Size of the auth hash of a session, in bytes.
+pub const AUTH_HASH_LEN: usize = 32;
+
+/// Long-term Ed25519 signing key of a node.
+pub struct NodeIdentity {
+    key_pair: Ed25519KeyPair,
+}
+
+impl NodeIdentity {
+    /// Generates a new signing key, encoded as PKCS#8 document.
+    pub fn generate() -> Result<Vec<u8>, RandomGenerationError> {
+        let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())?;
+        Ok(document.as_ref().to_vec())
+    }
+
+    /// Loads a signing key from its PKCS#8 document.
+    pub fn from_pkcs8(document: &[u8]) -> Result<Self, ConfigError> {
+        let key_pair = Ed25519KeyPair::from_pkcs8(document)
+            .map_err(|_error| ConfigError::InvalidSigningKey)?;
+        Ok(NodeIdentity { key_pair })
+    }
+
+    pub fn public_key(&self) -> Vec<u8> {
+        self.key_pair.public_key().as_ref().to_vec()
+    }
+
+    /// Signs a message built with `signed_message`.
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.key_pair.sign(message).as_ref().to_vec()
+    }
+}
+
+/// Builds the signed message of a node statement about a commitment.
+///
+/// `kind` separates the statements, e.g. a commitment from the reveal of its opening, so a
+/// signature can't be replayed as another statement. Variable-length parts are length-prefixed.
+pub fn signed_message(kind: &str, node_id: u16, commitment_id: u128, parts: &[&[u8]]) -> Vec<u8> {
+    let mut message = SIGNATURE_DOMAIN.to_vec();
+    for part in [kind.as_bytes()].iter().chain(parts) {
+        message.extend_from_slice(&(part.len() as u64).to_le_bytes());
+        message.extend_from_slice(part);
+    }
+    message.extend_from_slice(&node_id.to_le_bytes());
+    message.extend_from_slice(&commitment_id.to_le_bytes());
+    message
+}
+
+/// Message signed by a node for its commitment, or co-commitment, in the mode of the session.
+pub fn commitment_message(commitment: &CommitmentForRandom) -> Vec<u8> {
+    signed_message(
+        "commitment",
+        commitment.node_id,
+        commitment.commitment_id,
+        &[
+            &commitment.commitment,
+            commitment.random_mode.as_str().as_bytes(),
+        ],
+    )
+}
+
+/// Message signed by a dealer releasing the co-commitments of a session it left out of the draw.
+pub fn release_message(node_id: u16, commitment_id: u128) -> Vec<u8> {
+    signed_message("release", node_id, commitment_id, &[])
+}
+
+/// Message signed by a node for the reveal of its opening in the role it holds in the session, so
+/// the reveal of a dealer entry can't be passed off as the reveal of a co-commitment.
+pub fn reveal_message(
+    node_id: u16,
+    commitment_id: u128,
+    role: Role,
+    commitment: &[u8],
+    opening: &[u8],
+) -> Vec<u8> {
+    signed_message(
+        "reveal",
+        node_id,
+        commitment_id,
+        &[role.to_string().as_bytes(), commitment, opening],
+    )
+}
+
+/// SHA-256 hash of the secret a client proves in the reveal requests of its session.
+pub fn auth_hash(auth_proof: &[u8]) -> Vec<u8> {
+    digest(&SHA256, auth_proof).as_ref().to_vec()
+}
+
+/// Whether the auth proof of a reveal request is the secret of the session auth hash, sessions
+/// without an auth hash are revealed to any request.
+pub fn verify_auth_proof(session_hash: Option<&[u8]>, auth_proof: &[u8]) -> bool {
+    // comparing the hashes only leaks the hash of the guess
+    session_hash.is_none_or(|expected| auth_hash(auth_proof) == expected)
+}
+
+/// Verifies an Ed25519 signature of the message.
+pub fn verify_signature(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(message, signature)
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let identity = NodeIdentity::from_pkcs8(&NodeIdentity::generate().unwrap()).unwrap();
+        let message = signed_message("commitment", 1, 123, &[b"commitment"]);
+        let signature = identity.sign(&message);
+
+        assert!(verify_signature(
+            &identity.public_key(),
+            &message,
+            &signature
+        ));
+
+        // the signature covers the node, the session and the statement kind
+        for other in [
+            signed_message("commitment", 2, 123, &[b"commitment"]),
+            signed_message("commitment", 1, 124, &[b"commitment"]),
+            signed_message("reveal", 1, 123, &[b"commitment"]),
+            signed_message("commitment", 1, 123, &[b"commitmen", b"t"]),
+        ] {
+            assert!(!verify_signature(
+                &identity.public_key(),
+                &other,
+                &signature
+            ));
+        }
+    }
+
+    #[test]
+    fn test_reveal_message_role() {
+        // a reveal signed for one role doesn't verify for the other
+        assert_ne!(
+            reveal_message(1, 123, Role::Dealer, b"commitment", b"opening"),
+            reveal_message(1, 123, Role::CoCommitter, b"commitment", b"opening")
+        );
+    }
+
+    #[test]
+    fn test_invalid_signing_key() {
+        assert!(matches!(
+            NodeIdentity::from_pkcs8(&[0_u8; 16]),
+            Err(ConfigError::InvalidSigningKey)
+        ));
+    }
+
+    #[test]
+    fn test_verify_auth_proof() {
+        let hash = auth_hash(b"secret");
+        assert_eq!(hash.len(), AUTH_HASH_LEN);
+        assert!(verify_auth_proof(Some(&hash), b"secret"));
+        assert!(!verify_auth_proof(Some(&hash), b"guess"));
+        assert!(!verify_auth_proof(Some(&hash), b""));
+        assert!(verify_auth_proof(None, b""));
+    }
+}
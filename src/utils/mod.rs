@@ -1,5 +1,11 @@
 pub mod commitment;
 pub mod config;
+pub mod derivation;
 pub mod errors;
+pub mod extraction;
+pub mod identity;
 pub mod peers;
 pub mod random;
+pub mod sealing;
+pub mod tls;
+pub mod transcript;
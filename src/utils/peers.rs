@@ -1,15 +1,25 @@
+use std::{fs, time::Duration};
+
 use futures::prelude::*;
 use futures::stream::FuturesUnordered;
-use reqwest::Client;
+use reqwest::{Certificate, Client, Identity};
+use tokio::time::{sleep, timeout};
 use tracing::{event, Level};
 
 use crate::{
-    cache::state::CommitmentForRandom,
-    utils::{config::get_peer_count, errors::CommitmentGenerationError},
+    cache::state::{
+        CommitmentForRandom, ProofRequest, ReleaseRequest, RevealRequest, RevealResponse,
+        SessionProof,
+    },
+    utils::{
+        commitment::Commitment,
+        config::{NodeConfig, PeerConfig, RandomMode},
+        errors::{CommitmentGenerationError, ConfigError},
+        identity::{commitment_message, verify_signature},
+        tls::read_certificates,
+    },
 };
 
-use super::config::{get_mpc_threshold, get_node_id, get_port, get_project, get_service};
-
 pub fn get_commit_to_random_endpoint() -> String {
     "/commit-random".to_owned()
 }
@@ -18,6 +28,10 @@ pub fn get_co_commit_to_random_endpoint() -> String {
     "/co-commit-random".to_owned()
 }
 
+pub fn get_release_co_commitment_endpoint() -> String {
+    "/co-commit-random/release".to_owned()
+}
+
 pub fn get_nodes_endpoint() -> String {
     "/nodes".to_owned()
 }
@@ -26,56 +40,219 @@ pub fn get_reveal_random_endpoint() -> String {
     "/reveal-random".to_owned()
 }
 
-pub fn get_node_address() -> String {
-    get_peer_address(get_node_id().parse::<u16>().unwrap())
+pub fn get_session_proof_endpoint() -> String {
+    "/session/:commitment_id/proof".to_owned()
 }
 
-pub fn get_peer_hostname(index: u16) -> String {
-    format!("{}_{}_{}", get_project(), get_service(), index)
+pub fn get_session_range_endpoint() -> String {
+    "/session/:commitment_id/range".to_owned()
 }
 
-pub fn get_peer_address(index: u16) -> String {
-    format!(
-        "http://{}:{}",
-        get_peer_hostname(index),
-        get_peer_port(index)
-    )
+pub fn get_session_shuffle_endpoint() -> String {
+    "/session/:commitment_id/shuffle".to_owned()
+}
+
+pub fn get_session_sample_endpoint() -> String {
+    "/session/:commitment_id/sample".to_owned()
 }
 
-fn get_peer_endpoint(index: u16) -> String {
-    format!("{}/co-commit-random", get_peer_address(index))
+pub fn get_session_weighted_endpoint() -> String {
+    "/session/:commitment_id/weighted".to_owned()
 }
 
-#[allow(unused_variables)]
-pub fn get_peer_port(node_number: u16) -> u16 {
-    get_port().parse::<u16>().unwrap()
+pub fn get_session_stream_endpoint() -> String {
+    "/session/:commitment_id/stream".to_owned()
 }
 
-pub fn get_node_addresses() -> Vec<String> {
-    let mut peers: Vec<String> = Vec::new();
-    let num_nodes = get_peer_count().parse::<u16>().unwrap();
+pub fn get_audit_endpoint() -> String {
+    "/audit".to_owned()
+}
+
+pub fn get_audit_requesters_endpoint() -> String {
+    "/audit/requesters".to_owned()
+}
+
+// fills the commitment id of a session endpoint
+fn format_session_endpoint(endpoint: &str, commitment_id: u128) -> String {
+    endpoint.replace(":commitment_id", &commitment_id.to_string())
+}
+
+pub fn get_session_proof_path(commitment_id: u128) -> String {
+    format_session_endpoint(&get_session_proof_endpoint(), commitment_id)
+}
 
-    for index in 1..num_nodes + 1 {
-        peers.push(get_peer_address(index));
+pub fn get_session_range_path(commitment_id: u128) -> String {
+    format_session_endpoint(&get_session_range_endpoint(), commitment_id)
+}
+
+pub fn get_session_shuffle_path(commitment_id: u128) -> String {
+    format_session_endpoint(&get_session_shuffle_endpoint(), commitment_id)
+}
+
+pub fn get_session_sample_path(commitment_id: u128) -> String {
+    format_session_endpoint(&get_session_sample_endpoint(), commitment_id)
+}
+
+pub fn get_session_weighted_path(commitment_id: u128) -> String {
+    format_session_endpoint(&get_session_weighted_endpoint(), commitment_id)
+}
+
+pub fn get_session_stream_path(commitment_id: u128) -> String {
+    format_session_endpoint(&get_session_stream_endpoint(), commitment_id)
+}
+
+pub fn get_node_address(config: &NodeConfig) -> String {
+    get_peer_address(config, config.node_id).unwrap_or_default()
+}
+
+pub fn get_peer_hostname(config: &NodeConfig, index: u16) -> String {
+    format!("{}_{}_{}", config.project, config.service, index)
+}
+
+// configured peer table, or the docker-compose services when no table is configured
+pub fn get_peers(config: &NodeConfig) -> Vec<PeerConfig> {
+    if !config.peers.is_empty() {
+        return config.peers.clone();
     }
 
-    peers
+    let scheme = if config.tls_enabled() {
+        "https"
+    } else {
+        "http"
+    };
+    // all the services share the peer listener port
+    let url = |index: u16, port: u16| {
+        format!("{}://{}:{}", scheme, get_peer_hostname(config, index), port)
+    };
+    (1..config.num_nodes + 1)
+        .map(|index| PeerConfig {
+            id: index,
+            url: url(index, config.port),
+            public_key: None,
+            peer_url: config
+                .peer_listen
+                .map(|peer_listen| url(index, peer_listen.port())),
+        })
+        .collect()
+}
+
+pub fn get_peer_address(config: &NodeConfig, node_id: u16) -> Option<String> {
+    get_peers(config)
+        .into_iter()
+        .find(|peer| peer.id == node_id)
+        .map(|peer| peer.url.trim_end_matches('/').to_owned())
+}
+
+// public key of the node in the peer table, if configured, nodes outside of the table are an error
+pub fn get_peer_public_key(
+    config: &NodeConfig,
+    node_id: u16,
+) -> Result<Option<Vec<u8>>, ConfigError> {
+    get_peers(config)
+        .into_iter()
+        .find(|peer| peer.id == node_id)
+        .ok_or(ConfigError::InvalidNodeId(node_id))?
+        .decode_public_key()
+}
+
+// verifies the signature of a node message. Messages of nodes outside of the peer table are
+// rejected, and unsigned messages are only accepted while no node of the table has a public key
+pub fn verify_peer_signature(
+    config: &NodeConfig,
+    node_id: u16,
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    match get_peer_public_key(config, node_id) {
+        Ok(Some(public_key)) => verify_signature(&public_key, message, signature),
+        Ok(None) => !get_peers(config)
+            .iter()
+            .any(|peer| peer.public_key.is_some()),
+        Err(_) => false,
+    }
 }
 
-fn get_peer_addresses(node_id: u16, num_nodes: u16) -> Vec<String> {
-    let mut peers: Vec<String> = Vec::new();
+// co-commitments go to the internal peer listener of the node, if it has one
+fn get_peer_endpoint(peer: &PeerConfig) -> String {
+    format!(
+        "{}{}",
+        get_peer_url(peer),
+        get_co_commit_to_random_endpoint()
+    )
+}
+
+// the peer routes of a node are served on its peer url, or on its url without one
+fn get_peer_url(peer: &PeerConfig) -> &str {
+    peer.peer_url
+        .as_ref()
+        .unwrap_or(&peer.url)
+        .trim_end_matches('/')
+}
+
+pub fn get_node_addresses(config: &NodeConfig) -> Vec<String> {
+    get_peers(config)
+        .iter()
+        .map(|peer| peer.url.trim_end_matches('/').to_owned())
+        .collect()
+}
 
-    for index in 1..num_nodes + 1 {
+// co-commitment endpoints of the other nodes, with the id of the node behind each of them
+fn get_peer_addresses(config: &NodeConfig) -> Vec<(u16, String)> {
+    get_peers(config)
+        .iter()
         // Skip generating address for the current node (node_id).
-        if index == node_id {
-            continue;
+        .filter(|peer| peer.id != config.node_id)
+        .map(|peer| (peer.id, get_peer_endpoint(peer)))
+        .collect()
+}
+
+// creates the long-lived client of the peer requests, its connections are pooled across draws
+pub fn create_peer_client(config: &NodeConfig) -> Result<Client, ConfigError> {
+    let mut builder = Client::builder()
+        .connect_timeout(config.peer_connect_timeout())
+        .tcp_keepalive(config.peer_keepalive())
+        .pool_idle_timeout(config.peer_keepalive());
+
+    // over mutual TLS, peers are only trusted through the configured CA and the node presents its certificate
+    let tls = if let (Some(ca_file), Some(cert_file), Some(key_file)) = (
+        &config.tls_ca_file,
+        &config.tls_cert_file,
+        &config.tls_key_file,
+    ) {
+        let read = |path| fs::read(path).map_err(|err| ConfigError::File(err.to_string()));
+        let tls_error = |err: reqwest::Error| ConfigError::Tls(err.to_string());
+        let mut identity = read(cert_file)?;
+        identity.extend(read(key_file)?);
+
+        builder = builder
+            .use_rustls_tls()
+            .tls_built_in_root_certs(false)
+            .https_only(true)
+            .identity(Identity::from_pem(&identity).map_err(tls_error)?);
+        for certificate in read_certificates(ca_file)? {
+            builder = builder
+                .add_root_certificate(Certificate::from_der(&certificate).map_err(tls_error)?);
         }
+        true
+    } else {
+        false
+    };
 
-        let peer_address = get_peer_endpoint(index);
-        peers.push(peer_address);
-    }
+    // over TLS, http/2 is negotiated with ALPN, without TLS it is used with prior knowledge
+    let builder = match (config.peer_http2, tls) {
+        (true, true) => builder
+            .http2_keep_alive_interval(config.peer_keepalive())
+            .http2_keep_alive_while_idle(true),
+        (true, false) => builder
+            .http2_prior_knowledge()
+            .http2_keep_alive_interval(config.peer_keepalive())
+            .http2_keep_alive_while_idle(true),
+        (false, _) => builder.http1_only(),
+    };
 
-    peers
+    builder
+        .build()
+        .map_err(|err| ConfigError::Tls(err.to_string()))
 }
 
 // sends commitment to peer
@@ -105,55 +282,275 @@ pub async fn send_commitment_request(
     Ok(response)
 }
 
+// asks a peer to drop the co-commitment the dealer didn't use
+pub async fn send_release_request(
+    address: &str,
+    request: &ReleaseRequest,
+    http_client: Option<Client>,
+) -> Result<(), reqwest::Error> {
+    event!(
+        Level::DEBUG,
+        "utils::peer::send_release_request {}",
+        address
+    );
+    let client = match http_client {
+        Some(value) => value,
+        None => Client::new(),
+    };
+
+    client
+        .post(address)
+        .json(request)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+// requests the opening of a commitment from a node
+pub async fn send_reveal_request(
+    address: &str,
+    request: RevealRequest,
+    http_client: Option<Client>,
+) -> Result<RevealResponse, reqwest::Error> {
+    event!(Level::DEBUG, "utils::peer::send_reveal_request {}", address);
+    let client = match http_client {
+        Some(value) => value,
+        None => Client::new(),
+    };
+
+    let response = client
+        .post(address)
+        .json(&request)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<RevealResponse>()
+        .await?;
+
+    Ok(response)
+}
+
+// requests the transcript of a session from its dealer
+pub async fn send_proof_request(
+    address: &str,
+    http_client: Option<Client>,
+) -> Result<SessionProof, reqwest::Error> {
+    event!(Level::DEBUG, "utils::peer::send_proof_request {}", address);
+    let client = match http_client {
+        Some(value) => value,
+        None => Client::new(),
+    };
+
+    let response = client
+        .get(address)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<SessionProof>()
+        .await?;
+
+    Ok(response)
+}
+
+pub async fn send_finalize_proof_request(
+    address: &str,
+    request: ProofRequest,
+    http_client: Option<Client>,
+) -> Result<SessionProof, reqwest::Error> {
+    event!(
+        Level::DEBUG,
+        "utils::peer::send_finalize_proof_request {}",
+        address
+    );
+    let client = match http_client {
+        Some(value) => value,
+        None => Client::new(),
+    };
+
+    let response = client
+        .post(address)
+        .json(&request)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<SessionProof>()
+        .await?;
+
+    Ok(response)
+}
+
+// verifies the peer co-commitment, proofs are over the peer's own commitment without the dealer commitment
+fn verify_co_commitment(
+    config: &NodeConfig,
+    request: &CommitmentForRandom,
+    response: &CommitmentForRandom,
+) -> bool {
+    if response.commitment_id != request.commitment_id
+        || response.random_mode != request.random_mode
+    {
+        return false;
+    }
+
+    // co-commitments can't be substituted without the peer's signing key
+    if !verify_peer_signature(
+        config,
+        response.node_id,
+        &commitment_message(response),
+        &response.signature,
+    ) {
+        return false;
+    }
+
+    let (dealer_commitment, co_commitment) = match (
+        Commitment::from_slice(&request.commitment),
+        Commitment::from_slice(&response.commitment),
+    ) {
+        (Ok(dealer_commitment), Ok(co_commitment)) => (dealer_commitment, co_commitment),
+        _ => return false,
+    };
+
+    // rejects commitments derived from other nodes' commitments
+    let commitment = co_commitment - dealer_commitment;
+    if !commitment.verify_knowledge(
+        &response.knowledge_proof,
+        response.commitment_id,
+        response.node_id,
+    ) {
+        return false;
+    }
+
+    // scalar contributions are aggregated mod the group order and can't overflow
+    if request.random_mode == RandomMode::Scalar {
+        return true;
+    }
+
+    commitment.verify_range(
+        &response.range_proof,
+        response.commitment_id,
+        response.node_id,
+    )
+}
+
+// sends commitment to peer, retrying failed and timed out requests with an exponential backoff
+async fn send_commitment_with_retries(
+    address: &str,
+    commitment_for_random: CommitmentForRandom,
+    http_client: Option<Client>,
+    request_timeout: Duration,
+    retries: u32,
+    backoff: Duration,
+) -> Option<CommitmentForRandom> {
+    for attempt in 0..=retries {
+        if attempt > 0 {
+            sleep(backoff * 2_u32.saturating_pow(attempt - 1)).await;
+        }
+
+        let request =
+            send_commitment_request(address, commitment_for_random.clone(), http_client.clone());
+        match timeout(request_timeout, request).await {
+            Ok(Ok(response)) => return Some(response),
+            Ok(Err(err)) => {
+                event!(
+                    Level::ERROR,
+                    "utils::peer::send_commitment_with_retries::error {} {:?}",
+                    attempt,
+                    err.without_url()
+                );
+            }
+            Err(_) => {
+                event!(
+                    Level::ERROR,
+                    "utils::peer::send_commitment_with_retries::timeout {} {}",
+                    attempt,
+                    address
+                );
+            }
+        }
+    }
+
+    None
+}
+
+// collects co-commitments from the peers, returning as soon as the quorum is reached
 pub async fn get_commitment_from_peers(
+    config: &NodeConfig,
     commitment_for_random: CommitmentForRandom,
     http_client: Option<Client>,
 ) -> Result<Vec<CommitmentForRandom>, CommitmentGenerationError> {
     event!(Level::DEBUG, "utils::peer::get_commitment_from_peers");
 
-    let num_nodes = get_peer_count().parse::<u16>().unwrap();
-    let initial_peers = get_peer_addresses(get_node_id().parse::<u16>().unwrap(), num_nodes);
-    let threshold = (get_mpc_threshold().parse::<f32>().unwrap() * num_nodes as f32).floor(); // 2/3 of num_nodes
+    let initial_peers = get_peer_addresses(config);
+
+    let quorum = config.quorum();
 
     let mut futures = FuturesUnordered::new();
 
-    for address in initial_peers {
+    for (node_id, address) in initial_peers {
         let commitment = commitment_for_random.clone();
         let http_client_clone = http_client.clone();
+        let (request_timeout, retries, backoff) = (
+            config.peer_timeout(),
+            config.peer_retries,
+            config.peer_backoff(),
+        );
         let fut = async move {
-            let response = send_commitment_request(&address, commitment, http_client_clone).await;
-            if response.is_err() {
-                let error = response.err().unwrap() as reqwest::Error;
-                event!(
-                    Level::ERROR,
-                    "utils::peer::get_commitment_from_peers::error {:?}",
-                    error
-                );
-                Err(error.without_url())
-            } else {
-                response
-            }
+            let response = send_commitment_with_retries(
+                &address,
+                commitment,
+                http_client_clone,
+                request_timeout,
+                retries,
+                backoff,
+            )
+            .await;
+            (node_id, response)
         };
 
         futures.push(tokio::spawn(fut));
     }
 
-    // Wait for all futures to complete and collect the responses.
+    // Wait for the futures until the quorum is reached, or can't be reached anymore.
     let mut responses: Vec<CommitmentForRandom> = Vec::new();
-    while let Some(result) = futures.next().await {
+    while responses.len() < quorum && responses.len() + futures.len() >= quorum {
+        let Some(result) = futures.next().await else {
+            break;
+        };
         match result {
-            Ok(join_response) => match join_response {
-                Ok(commitment_response) => {
+            // the co-commitment has to come from the node it was requested from
+            Ok((node_id, Some(commitment_response))) if commitment_response.node_id != node_id => {
+                event!(
+                    Level::ERROR,
+                    "utils::peer::get_commitment_from_peers::co-commitment of node {} from node {}",
+                    commitment_response.node_id,
+                    node_id
+                );
+            }
+            Ok((_, Some(commitment_response))) => {
+                // a node contributes once, and the dealer contributes its own commitment only
+                if commitment_response.node_id == config.node_id
+                    || responses
+                        .iter()
+                        .any(|response| response.node_id == commitment_response.node_id)
+                {
+                    event!(
+                        Level::ERROR,
+                        "utils::peer::get_commitment_from_peers::duplicate co-commitment from node {}",
+                        commitment_response.node_id
+                    );
+                } else if verify_co_commitment(config, &commitment_for_random, &commitment_response)
+                {
                     responses.push(commitment_response);
-                }
-                Err(err) => {
+                } else {
                     event!(
                         Level::ERROR,
-                        "utils::peer::get_commitment_from_peers::reading http futures {:?}",
-                        err
+                        "utils::peer::get_commitment_from_peers::invalid co-commitment from node {}",
+                        commitment_response.node_id
                     );
                 }
-            },
+            }
+            Ok((_, None)) => {}
             Err(err) => {
                 event!(
                     Level::ERROR,
@@ -164,40 +561,153 @@ pub async fn get_commitment_from_peers(
         }
     }
 
+    // late peers are not part of the draw, their commitments expire from their caches
+    if !futures.is_empty() {
+        event!(
+            Level::DEBUG,
+            "utils::peer::get_commitment_from_peers::cancelled {}",
+            futures.len()
+        );
+        for handle in futures.iter() {
+            handle.abort();
+        }
+    }
+
     event!(
         Level::DEBUG,
         "utils::peer::get_commitment_from_peers::futures_count {}",
         responses.len()
     );
 
-    if (responses.len() as f32) >= threshold {
+    if responses.len() >= quorum {
         Ok(responses)
     } else {
         Err(CommitmentGenerationError)
     }
 }
 
+/// Releases the co-commitments of the peers left out of a draw, so that they are audited as
+/// released on the peers instead of withheld by the dealer.
+///
+/// Peers that never stored a co-commitment for the session, e.g. whose request was cancelled before
+/// it was handled, reject the release, which is only logged.
+pub async fn release_co_commitments(
+    config: &NodeConfig,
+    request: ReleaseRequest,
+    used: &[u16],
+    http_client: Option<Client>,
+) {
+    event!(Level::DEBUG, "utils::peer::release_co_commitments");
+
+    let releases = get_peers(config)
+        .into_iter()
+        .filter(|peer| peer.id != config.node_id && !used.contains(&peer.id))
+        .map(|peer| {
+            let address = format!(
+                "{}{}",
+                get_peer_url(&peer),
+                get_release_co_commitment_endpoint()
+            );
+            let (request, http_client) = (&request, http_client.clone());
+            async move {
+                match timeout(
+                    config.peer_timeout(),
+                    send_release_request(&address, request, http_client),
+                )
+                .await
+                {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => event!(
+                        Level::DEBUG,
+                        "utils::peer::release_co_commitments::node {} {:?}",
+                        peer.id,
+                        err
+                    ),
+                    Err(_) => event!(
+                        Level::DEBUG,
+                        "utils::peer::release_co_commitments::node {} timed out",
+                        peer.id
+                    ),
+                }
+            }
+        });
+    future::join_all(releases).await;
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use tokio::{net::TcpListener, time::Instant};
+
     use super::*;
+    use crate::utils::identity::NodeIdentity;
+
+    // accepts connections without ever responding, counting the connection attempts
+    async fn start_hanging_peer() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                connections.push(stream);
+            }
+        });
+        (address, attempts)
+    }
+
+    fn peer(id: u16, url: &str) -> PeerConfig {
+        PeerConfig {
+            id,
+            url: url.to_string(),
+            public_key: None,
+            peer_url: None,
+        }
+    }
+
+    #[test]
+    fn test_get_session_paths() {
+        let commitment_id = u128::MAX;
+        assert_eq!(
+            get_session_proof_path(commitment_id),
+            format!("/session/{}/proof", commitment_id)
+        );
+        assert_eq!(get_session_range_path(123), "/session/123/range");
+        assert_eq!(get_session_stream_path(123), "/session/123/stream");
+    }
 
     #[test]
     fn test_get_peer_addresses() {
-        let node_id = 2;
-        let num_nodes = 5;
+        let config = NodeConfig {
+            node_id: 2,
+            num_nodes: 5,
+            ..NodeConfig::default()
+        };
 
-        let initial_peers = get_peer_addresses(node_id, num_nodes);
+        let initial_peers = get_peer_addresses(&config);
 
         // Ensure that the generated addresses do not contain the address for the current node.
-        assert!(!initial_peers.contains(&get_peer_endpoint(node_id)));
+        let own_peer = &get_peers(&config)[1];
+        assert!(!initial_peers.contains(&(2, get_peer_endpoint(own_peer))));
+        assert!(initial_peers.iter().all(|(node_id, address)| *address
+            == get_peer_endpoint(&get_peers(&config)[*node_id as usize - 1])));
     }
 
     #[test]
     fn test_get_peer_addresses_no_duplicate() {
-        let node_id = 2; // Example node ID
-        let num_nodes = 5; // Example number of nodes
+        let config = NodeConfig {
+            node_id: 2,   // Example node ID
+            num_nodes: 5, // Example number of nodes
+            ..NodeConfig::default()
+        };
 
-        let initial_peers = get_peer_addresses(node_id, num_nodes);
+        let initial_peers = get_peer_addresses(&config);
 
         // Ensure that the generated addresses do not contain duplicates
         assert_eq!(
@@ -211,12 +721,297 @@ mod tests {
 
     #[test]
     fn test_get_peer_addresses_count() {
-        let node_id = 2; // Example node ID
-        let num_nodes = 5; // Example number of nodes
+        let config = NodeConfig {
+            node_id: 2,   // Example node ID
+            num_nodes: 5, // Example number of nodes
+            ..NodeConfig::default()
+        };
 
-        let initial_peers = get_peer_addresses(node_id, num_nodes);
+        let initial_peers = get_peer_addresses(&config);
 
         // Ensure that the number of received addresses is num_nodes - 1
-        assert_eq!(initial_peers.len(), (num_nodes - 1) as usize);
+        assert_eq!(initial_peers.len(), (config.num_nodes - 1) as usize);
+    }
+
+    #[test]
+    fn test_get_peer_addresses_from_table() {
+        let config = NodeConfig {
+            node_id: 2,
+            num_nodes: 3,
+            peers: vec![
+                PeerConfig {
+                    id: 1,
+                    url: "http://10.0.0.1:7000/".to_string(),
+                    public_key: None,
+                    peer_url: None,
+                },
+                PeerConfig {
+                    id: 2,
+                    url: "http://10.0.0.2:7100".to_string(),
+                    public_key: None,
+                    peer_url: None,
+                },
+                PeerConfig {
+                    id: 3,
+                    url: "http://[2001:db8::3]:7200".to_string(),
+                    public_key: None,
+                    peer_url: None,
+                },
+            ],
+            ..NodeConfig::default()
+        };
+
+        assert_eq!(
+            get_peer_addresses(&config),
+            vec![
+                (1, "http://10.0.0.1:7000/co-commit-random".to_string()),
+                (3, "http://[2001:db8::3]:7200/co-commit-random".to_string())
+            ]
+        );
+        assert_eq!(get_node_address(&config), "http://10.0.0.2:7100");
+        assert_eq!(get_peer_address(&config, 4), None);
+    }
+
+    #[test]
+    fn test_verify_peer_signature() {
+        let identity = NodeIdentity::from_pkcs8(&NodeIdentity::generate().unwrap()).unwrap();
+        let peer = |id: u16, public_key: Option<String>| PeerConfig {
+            id,
+            url: format!("http://10.0.0.{}:7000", id),
+            public_key,
+            peer_url: None,
+        };
+        let message = b"message";
+        let signature = identity.sign(message);
+
+        // without any public key, messages of the nodes in the table are unauthenticated
+        let mut config = NodeConfig {
+            peers: vec![peer(1, None), peer(2, None)],
+            ..NodeConfig::default()
+        };
+        assert!(verify_peer_signature(&config, 2, message, &[]));
+        assert!(!verify_peer_signature(&config, 999, message, &[]));
+
+        // once a node has a public key, every node has to sign
+        config.peers[0].public_key = Some(hex::encode(identity.public_key()));
+        assert!(verify_peer_signature(&config, 1, message, &signature));
+        assert!(!verify_peer_signature(&config, 1, message, &[]));
+        assert!(!verify_peer_signature(&config, 2, message, &signature));
+
+        // a key that can't be decoded doesn't turn the signature off
+        config.peers[0].public_key = Some("not hex".to_string());
+        assert!(!verify_peer_signature(&config, 1, message, &signature));
+        assert_eq!(
+            get_peer_public_key(&config, 1),
+            Err(ConfigError::InvalidPublicKey(1))
+        );
+    }
+
+    #[test]
+    fn test_get_peer_addresses_peer_listen() {
+        let config = NodeConfig {
+            node_id: 1,
+            num_nodes: 2,
+            peer_listen: Some("0.0.0.0:7100".parse().unwrap()),
+            ..NodeConfig::default()
+        };
+
+        // co-commitments go to the peer listener, clients are given the public address
+        assert_eq!(
+            get_peer_addresses(&config),
+            vec![(
+                2,
+                "http://random_pedersen_node_2:7100/co-commit-random".to_string()
+            )]
+        );
+        assert_eq!(
+            get_node_address(&config),
+            "http://random_pedersen_node_1:7000"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_commitment_from_peers_early_quorum() {
+        let commitment_id = 123_u128;
+        let (dealer_commitment, dealer_opening) = Commitment::new(1000);
+        let (commitment, opening) = Commitment::new(500);
+        let co_commitment = CommitmentForRandom::for_test(
+            2,
+            commitment_id,
+            &(commitment + dealer_commitment.clone()),
+            &opening,
+        );
+
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/co-commit-random")
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&co_commitment).unwrap())
+            .create_async()
+            .await;
+        let (hanging_address, _) = start_hanging_peer().await;
+
+        let config = NodeConfig {
+            node_id: 1,
+            num_nodes: 3,
+            mpc_threshold: 0.34,
+            peers: vec![
+                peer(1, "http://127.0.0.1:1"),
+                peer(2, &server.url()),
+                peer(3, &hanging_address),
+            ],
+            peer_timeout_ms: 10_000,
+            ..NodeConfig::default()
+        };
+        let request =
+            CommitmentForRandom::for_test(1, commitment_id, &dealer_commitment, &dealer_opening);
+
+        // returns once node 2 responds, without waiting for node 3 to time out
+        let start = Instant::now();
+        let responses = get_commitment_from_peers(&config, request, None)
+            .await
+            .unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].node_id, 2);
+        assert_eq!(responses[0].commitment, co_commitment.commitment);
+    }
+
+    #[tokio::test]
+    async fn test_get_commitment_from_peers_impersonation() {
+        let commitment_id = 123_u128;
+        let (dealer_commitment, dealer_opening) = Commitment::new(1000);
+        let (commitment, opening) = Commitment::new(500);
+        // a valid co-commitment of node 3, returned by node 2
+        let co_commitment = CommitmentForRandom::for_test(
+            3,
+            commitment_id,
+            &(commitment + dealer_commitment.clone()),
+            &opening,
+        );
+
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/co-commit-random")
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::to_string(&co_commitment).unwrap())
+            .create_async()
+            .await;
+
+        let config = NodeConfig {
+            node_id: 1,
+            num_nodes: 3,
+            mpc_threshold: 0.34,
+            peers: vec![
+                peer(1, "http://127.0.0.1:1"),
+                peer(2, &server.url()),
+                peer(3, "http://127.0.0.1:1"),
+            ],
+            peer_retries: 0,
+            ..NodeConfig::default()
+        };
+        let request =
+            CommitmentForRandom::for_test(1, commitment_id, &dealer_commitment, &dealer_opening);
+
+        // the co-commitment isn't counted for node 3, nor for node 2
+        assert!(get_commitment_from_peers(&config, request, None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_release_co_commitments() {
+        let mut used = mockito::Server::new_async().await;
+        let used_mock = used
+            .mock("POST", "/co-commit-random/release")
+            .expect(0)
+            .create_async()
+            .await;
+        let mut unused = mockito::Server::new_async().await;
+        let request = ReleaseRequest {
+            node_id: 1,
+            commitment_id: 123,
+            signature: Vec::new(),
+        };
+        let unused_mock = unused
+            .mock("POST", "/co-commit-random/release")
+            .match_body(mockito::Matcher::Json(
+                serde_json::to_value(&request).unwrap(),
+            ))
+            .with_status(204)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let config = NodeConfig {
+            node_id: 1,
+            num_nodes: 3,
+            peers: vec![
+                peer(1, "http://127.0.0.1:1"),
+                peer(2, &used.url()),
+                peer(3, &unused.url()),
+            ],
+            ..NodeConfig::default()
+        };
+
+        // only the peers left out of the draw are asked to release their co-commitments
+        release_co_commitments(&config, request, &[2], None).await;
+        used_mock.assert_async().await;
+        unused_mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_get_commitment_from_peers_retries() {
+        let (hanging_address, attempts) = start_hanging_peer().await;
+        let config = NodeConfig {
+            node_id: 1,
+            num_nodes: 2,
+            peers: vec![peer(1, "http://127.0.0.1:1"), peer(2, &hanging_address)],
+            peer_timeout_ms: 50,
+            peer_retries: 2,
+            peer_backoff_ms: 10,
+            ..NodeConfig::default()
+        };
+        let (commitment, opening) = Commitment::new(1000);
+        let request = CommitmentForRandom::for_test(1, 124, &commitment, &opening);
+
+        let result = get_commitment_from_peers(&config, request, None).await;
+        assert!(result.is_err());
+
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 3); // initial request and two retries
+    }
+
+    #[tokio::test]
+    async fn test_create_peer_client_http2() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let app =
+            axum::Router::new().route(&get_nodes_endpoint(), axum::routing::get(|| async { "[]" }));
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+
+        let client = create_peer_client(&NodeConfig::default()).unwrap();
+        let response = client
+            .get(format!("{}{}", address, get_nodes_endpoint()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.version(), reqwest::Version::HTTP_2);
+
+        let config = NodeConfig {
+            peer_http2: false,
+            ..NodeConfig::default()
+        };
+        let client = create_peer_client(&config).unwrap();
+        let response = client
+            .get(format!("{}{}", address, get_nodes_endpoint()))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.version(), reqwest::Version::HTTP_11);
     }
 }
@@ -1,3 +1,4 @@
+use curve25519_dalek_ng::scalar::Scalar;
 use ring::rand::{SecureRandom, SystemRandom};
 
 use super::errors::RandomGenerationError;
@@ -11,6 +12,14 @@ pub fn generate_random(length: usize) -> Result<Vec<u8>, RandomGenerationError>
     Ok(random_bytes)
 }
 
+// generates uniformly random scalar, reducing 64 random bytes mod the group order
+pub fn generate_random_scalar() -> Result<Scalar, RandomGenerationError> {
+    let random = generate_random(64)?;
+    let mut arr = [0; 64];
+    arr.copy_from_slice(&random[0..64]);
+    Ok(Scalar::from_bytes_mod_order_wide(&arr))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,172 @@
+use std::collections::HashSet;
+
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use super::errors::{CacheError, ConfigError, RandomGenerationError};
+
+/// Size of a store key, in bytes.
+pub const STORE_KEY_LEN: usize = 32;
+
+/// Opening sealed with a store key, tagged with the id of the key.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SealedOpening {
+    pub key_id: String,
+    pub nonce: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+/// ChaCha20-Poly1305 keys sealing the openings at rest.
+///
+/// The first key seals new entries, the other keys only open entries sealed before a rotation.
+pub struct StoreKeyring {
+    keys: Vec<(String, LessSafeKey)>,
+    random: SystemRandom,
+}
+
+impl StoreKeyring {
+    /// Generates a new key in the `key_id:hex` format.
+    pub fn generate(key_id: &str) -> Result<Zeroizing<String>, RandomGenerationError> {
+        let mut key = Zeroizing::new([0_u8; STORE_KEY_LEN]);
+        SystemRandom::new().fill(key.as_mut())?;
+        Ok(Zeroizing::new(format!("{}:{}", key_id, hex::encode(key))))
+    }
+
+    /// Parses keys in the `key_id:hex` format, the first key is the active one.
+    pub fn from_entries<'a>(
+        entries: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Option<Self>, ConfigError> {
+        let mut ids = HashSet::new();
+        let mut keys = Vec::new();
+        for entry in entries {
+            let (key_id, key) = entry
+                .trim()
+                .split_once(':')
+                .ok_or(ConfigError::InvalidStoreKey(String::new()))?;
+            let invalid = || ConfigError::InvalidStoreKey(key_id.to_string());
+            if key_id.is_empty() || !ids.insert(key_id) {
+                return Err(invalid());
+            }
+            let key = Zeroizing::new(hex::decode(key).map_err(|_error| invalid())?);
+            if key.len() != STORE_KEY_LEN {
+                return Err(invalid());
+            }
+            let key = UnboundKey::new(&CHACHA20_POLY1305, &key).map_err(|_error| invalid())?;
+            keys.push((key_id.to_string(), LessSafeKey::new(key)));
+        }
+
+        if keys.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(StoreKeyring {
+            keys,
+            random: SystemRandom::new(),
+        }))
+    }
+
+    /// Id of the key sealing new entries.
+    pub fn active_key_id(&self) -> &str {
+        &self.keys[0].0
+    }
+
+    /// Seals the opening with the active key, authenticating the associated data with it.
+    pub fn seal(&self, aad: &[u8], opening: &[u8]) -> Result<SealedOpening, CacheError> {
+        let (key_id, key) = &self.keys[0];
+        let mut nonce = [0_u8; NONCE_LEN];
+        self.random
+            .fill(&mut nonce)
+            .map_err(|_error| CacheError::Storage("failed to generate a nonce".to_string()))?;
+
+        let mut ciphertext = opening.to_vec();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(aad),
+            &mut ciphertext,
+        )
+        .map_err(|_error| CacheError::Storage("failed to seal the opening".to_string()))?;
+
+        Ok(SealedOpening {
+            key_id: key_id.clone(),
+            nonce: nonce.to_vec(),
+            ciphertext,
+        })
+    }
+
+    /// Opens a sealed opening with the key it is tagged with.
+    pub fn open(
+        &self,
+        aad: &[u8],
+        sealed: &SealedOpening,
+    ) -> Result<Zeroizing<Vec<u8>>, CacheError> {
+        let (_, key) = self
+            .keys
+            .iter()
+            .find(|(key_id, _)| *key_id == sealed.key_id)
+            .ok_or_else(|| CacheError::Storage(format!("unknown store key {}", sealed.key_id)))?;
+        let nonce = Nonce::try_assume_unique_for_key(&sealed.nonce)
+            .map_err(|_error| CacheError::Storage("invalid nonce".to_string()))?;
+
+        let mut opening = Zeroizing::new(sealed.ciphertext.clone());
+        let length = key
+            .open_in_place(nonce, Aad::from(aad), &mut opening)
+            .map_err(|_error| CacheError::Storage("failed to open the opening".to_string()))?
+            .len();
+        opening.truncate(length);
+        Ok(opening)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_rotate() {
+        let old = StoreKeyring::generate("old").unwrap();
+        let new = StoreKeyring::generate("new").unwrap();
+        let keyring = StoreKeyring::from_entries([old.as_str()]).unwrap().unwrap();
+
+        let sealed = keyring.seal(b"session", b"opening").unwrap();
+        assert_eq!(sealed.key_id, "old");
+        assert_ne!(sealed.ciphertext, b"opening");
+        assert_eq!(*keyring.open(b"session", &sealed).unwrap(), b"opening");
+
+        // the associated data is authenticated
+        assert!(keyring.open(b"other session", &sealed).is_err());
+
+        // after a rotation new entries are sealed with the new key, old entries still open
+        let rotated = StoreKeyring::from_entries([new.as_str(), old.as_str()])
+            .unwrap()
+            .unwrap();
+        assert_eq!(rotated.active_key_id(), "new");
+        assert_eq!(rotated.seal(b"session", b"opening").unwrap().key_id, "new");
+        assert_eq!(*rotated.open(b"session", &sealed).unwrap(), b"opening");
+
+        // entries sealed with a retired key can't be opened anymore
+        let retired = StoreKeyring::from_entries([new.as_str()]).unwrap().unwrap();
+        assert!(retired.open(b"session", &sealed).is_err());
+    }
+
+    #[test]
+    fn test_invalid_store_keys() {
+        assert_eq!(
+            StoreKeyring::from_entries(["key:0011"]).err(),
+            Some(ConfigError::InvalidStoreKey("key".to_string()))
+        );
+        assert_eq!(
+            StoreKeyring::from_entries(["key"]).err(),
+            Some(ConfigError::InvalidStoreKey(String::new()))
+        );
+
+        let key = StoreKeyring::generate("key").unwrap();
+        assert_eq!(
+            StoreKeyring::from_entries([key.as_str(), key.as_str()]).err(),
+            Some(ConfigError::InvalidStoreKey("key".to_string()))
+        );
+        assert!(StoreKeyring::from_entries([]).unwrap().is_none());
+    }
+}
@@ -0,0 +1,346 @@
+use std::{fs, io::BufReader, net::IpAddr, path::Path, sync::Arc};
+
+use axum::{extract::ConnectInfo, Extension, Router};
+use hyper::server::conn::Http;
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa, SanType};
+use rustls::{server::AllowAnyAnonymousOrAuthenticatedClient, RootCertStore, ServerConfig};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tracing::{event, Level};
+use webpki::{DnsNameRef, EndEntityCert, SubjectNameRef};
+
+use super::{config::NodeConfig, errors::ConfigError, peers::get_peers};
+
+/// Peer authenticated by its client certificate, attached to the requests of its connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeerCertificate {
+    pub node_id: u16,
+}
+
+/// Name of a node in its certificate, peers are identified by it over mutual TLS.
+pub fn get_certificate_name(node_id: u16) -> String {
+    format!("node-{}", node_id)
+}
+
+// reads the certificates of a PEM file
+pub(crate) fn read_certificates(path: &Path) -> Result<Vec<Vec<u8>>, ConfigError> {
+    let file = fs::File::open(path).map_err(|err| ConfigError::File(err.to_string()))?;
+    let certificates = rustls_pemfile::certs(&mut BufReader::new(file))
+        .map_err(|err| ConfigError::Tls(err.to_string()))?;
+    if certificates.is_empty() {
+        return Err(ConfigError::Tls(format!(
+            "no certificate in {}",
+            path.display()
+        )));
+    }
+    Ok(certificates)
+}
+
+// reads the PKCS#8 private key of a PEM file
+fn read_private_key(path: &Path) -> Result<Vec<u8>, ConfigError> {
+    let file = fs::File::open(path).map_err(|err| ConfigError::File(err.to_string()))?;
+    rustls_pemfile::pkcs8_private_keys(&mut BufReader::new(file))
+        .map_err(|err| ConfigError::Tls(err.to_string()))?
+        .into_iter()
+        .next()
+        .ok_or(ConfigError::Tls(format!(
+            "no private key in {}",
+            path.display()
+        )))
+}
+
+/// Creates the TLS config of the node listener, when TLS is configured.
+///
+/// Client certificates are optional, so clients can connect without one, while peers present a
+/// certificate issued by the configured CA.
+pub fn create_server_config(config: &NodeConfig) -> Result<Option<Arc<ServerConfig>>, ConfigError> {
+    let (ca_file, cert_file, key_file) = match (
+        &config.tls_ca_file,
+        &config.tls_cert_file,
+        &config.tls_key_file,
+    ) {
+        (Some(ca_file), Some(cert_file), Some(key_file)) => (ca_file, cert_file, key_file),
+        (None, None, None) => return Ok(None),
+        _ => return Err(ConfigError::IncompleteTls),
+    };
+
+    let mut roots = RootCertStore::empty();
+    for certificate in read_certificates(ca_file)? {
+        roots
+            .add(&rustls::Certificate(certificate))
+            .map_err(|err| ConfigError::Tls(err.to_string()))?;
+    }
+    let certificates = read_certificates(cert_file)?
+        .into_iter()
+        .map(rustls::Certificate)
+        .collect();
+    let key = rustls::PrivateKey(read_private_key(key_file)?);
+
+    let mut server_config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_client_cert_verifier(AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed())
+        .with_single_cert(certificates, key)
+        .map_err(|err| ConfigError::Tls(err.to_string()))?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(Some(Arc::new(server_config)))
+}
+
+/// Returns the id of the peer the certificate was issued to.
+///
+/// The certificate chain is verified against the CA during the handshake, here the certificate
+/// has to name one of the other nodes of the peer table.
+pub fn get_certificate_node_id(config: &NodeConfig, certificate: &[u8]) -> Option<u16> {
+    let certificate = EndEntityCert::try_from(certificate).ok()?;
+    get_peers(config)
+        .into_iter()
+        .map(|peer| peer.id)
+        .filter(|node_id| *node_id != config.node_id)
+        .find(|node_id| {
+            let name = get_certificate_name(*node_id);
+            DnsNameRef::try_from_ascii_str(&name).is_ok_and(|name| {
+                certificate
+                    .verify_is_valid_for_subject_name(SubjectNameRef::DnsName(name))
+                    .is_ok()
+            })
+        })
+}
+
+/// Serves the router over TLS, attaching the remote address and the authenticated peer to the
+/// requests of its connections.
+pub async fn serve_tls(
+    listener: TcpListener,
+    app: Router,
+    server_config: Arc<ServerConfig>,
+    config: Arc<NodeConfig>,
+) {
+    let acceptor = TlsAcceptor::from(server_config);
+    loop {
+        let (stream, address) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                event!(Level::ERROR, "utils::tls::serve_tls::accept {:?}", err);
+                continue;
+            }
+        };
+
+        let acceptor = acceptor.clone();
+        let app = app.clone();
+        let config = config.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    event!(
+                        Level::DEBUG,
+                        "utils::tls::serve_tls::handshake {} {:?}",
+                        address,
+                        err
+                    );
+                    return;
+                }
+            };
+
+            let peer = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certificates| certificates.first())
+                .and_then(|certificate| get_certificate_node_id(&config, &certificate.0));
+            let app = match peer {
+                Some(node_id) => app.layer(Extension(PeerCertificate { node_id })),
+                None => app,
+            }
+            .layer(Extension(ConnectInfo(address)));
+
+            if let Err(err) = Http::new().serve_connection(stream, app).await {
+                event!(
+                    Level::DEBUG,
+                    "utils::tls::serve_tls::connection {} {:?}",
+                    address,
+                    err
+                );
+            }
+        });
+    }
+}
+
+/// Generates the self-signed CA issuing the node certificates.
+pub fn generate_ca() -> Result<Certificate, rcgen::Error> {
+    let mut params = CertificateParams::new(Vec::new());
+    params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, "random-pedersen CA");
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    Certificate::from_params(params)
+}
+
+/// Generates the certificate of a node, valid for its certificate name and the given hosts.
+pub fn generate_node_certificate(
+    node_id: u16,
+    hosts: &[String],
+) -> Result<Certificate, rcgen::Error> {
+    let mut params = CertificateParams::new(Vec::new());
+    params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, get_certificate_name(node_id));
+    params.subject_alt_names = std::iter::once(get_certificate_name(node_id))
+        .chain(hosts.iter().cloned())
+        .map(|host| match host.parse::<IpAddr>() {
+            Ok(ip) => SanType::IpAddress(ip),
+            Err(_) => SanType::DnsName(host),
+        })
+        .collect();
+    Certificate::from_params(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, path::PathBuf};
+
+    use axum::routing::get;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::utils::{config::PeerConfig, peers::create_peer_client};
+
+    // writes a CA and the certificates of two nodes to a fresh directory, returning the config of
+    // each node
+    fn write_certificates(name: &str, address: &str) -> (PathBuf, Vec<NodeConfig>) {
+        let dir = temp_dir().join(format!("{}-{}", name, Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let ca = generate_ca().unwrap();
+        fs::write(dir.join("ca.pem"), ca.serialize_pem().unwrap()).unwrap();
+
+        let peers: Vec<PeerConfig> = [1, 2]
+            .into_iter()
+            .map(|id| PeerConfig {
+                id,
+                url: format!("https://{}/{}", address, id),
+                public_key: None,
+                peer_url: None,
+            })
+            .collect();
+        let configs = [1, 2]
+            .into_iter()
+            .map(|node_id| {
+                let certificate =
+                    generate_node_certificate(node_id, &["127.0.0.1".to_string()]).unwrap();
+                let cert_file = dir.join(format!("node-{}.pem", node_id));
+                let key_file = dir.join(format!("node-{}.key", node_id));
+                fs::write(
+                    &cert_file,
+                    certificate.serialize_pem_with_signer(&ca).unwrap(),
+                )
+                .unwrap();
+                fs::write(&key_file, certificate.serialize_private_key_pem()).unwrap();
+                NodeConfig {
+                    node_id,
+                    num_nodes: 2,
+                    peers: peers.clone(),
+                    tls_ca_file: Some(dir.join("ca.pem")),
+                    tls_cert_file: Some(cert_file),
+                    tls_key_file: Some(key_file),
+                    ..NodeConfig::default()
+                }
+            })
+            .collect();
+        (dir, configs)
+    }
+
+    #[test]
+    fn test_validate_tls() {
+        let (_dir, configs) = write_certificates("random-pedersen-test-tls-config", "127.0.0.1:1");
+        assert_eq!(configs[0].validate(), Ok(()));
+
+        let config = NodeConfig {
+            tls_key_file: None,
+            ..configs[0].clone()
+        };
+        assert_eq!(config.validate(), Err(ConfigError::IncompleteTls));
+
+        // peers are only reachable with https over mutual TLS
+        let mut config = configs[0].clone();
+        config.peers[1].url = "http://127.0.0.1:2".to_string();
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::InvalidPeerUrl(
+                "http://127.0.0.1:2".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_certificate_node_id() {
+        let (dir, configs) = write_certificates("random-pedersen-test-tls-names", "127.0.0.1:1");
+        let certificate = |node_id: u16| {
+            read_certificates(&dir.join(format!("node-{}.pem", node_id))).unwrap()[0].clone()
+        };
+
+        assert_eq!(
+            get_certificate_node_id(&configs[0], &certificate(2)),
+            Some(2)
+        );
+        assert_eq!(
+            get_certificate_node_id(&configs[1], &certificate(1)),
+            Some(1)
+        );
+        // a node doesn't call itself
+        assert_eq!(get_certificate_node_id(&configs[0], &certificate(1)), None);
+
+        let other = generate_node_certificate(3, &[]).unwrap();
+        let other = other
+            .serialize_der_with_signer(&generate_ca().unwrap())
+            .unwrap();
+        assert_eq!(get_certificate_node_id(&configs[0], &other), None);
+    }
+
+    #[tokio::test]
+    async fn test_serve_tls() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let (_dir, configs) = write_certificates("random-pedersen-test-tls-serve", &address);
+
+        // answers with the node id of the authenticated peer
+        let app = Router::new().route(
+            "/peer",
+            get(|peer: Option<Extension<PeerCertificate>>| async move {
+                peer.map(|Extension(peer)| peer.node_id.to_string())
+                    .unwrap_or_default()
+            }),
+        );
+        let server_config = create_server_config(&configs[1]).unwrap().unwrap();
+        tokio::spawn(serve_tls(
+            listener,
+            app,
+            server_config,
+            Arc::new(configs[1].clone()),
+        ));
+
+        let url = format!("https://{}/peer", address);
+        let client = create_peer_client(&configs[0]).unwrap();
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.version(), reqwest::Version::HTTP_2); // negotiated with ALPN
+        assert_eq!(response.text().await.unwrap(), "1");
+
+        // clients without a certificate are served, but not as a peer
+        let ca = fs::read(configs[0].tls_ca_file.as_ref().unwrap()).unwrap();
+        let client = reqwest::Client::builder()
+            .use_rustls_tls()
+            .add_root_certificate(reqwest::Certificate::from_pem(&ca).unwrap())
+            .build()
+            .unwrap();
+        let response = client.get(&url).send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "");
+
+        // the node certificates are only trusted through the CA
+        let response = reqwest::Client::builder()
+            .use_rustls_tls()
+            .build()
+            .unwrap()
+            .get(&url)
+            .send()
+            .await;
+        assert!(response.is_err());
+    }
+}
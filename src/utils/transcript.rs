@@ -0,0 +1,562 @@
+use std::collections::HashSet;
+
+use crate::{
+    cache::state::{CommitmentForRandom, RevealedRandom, Role, SessionProof},
+    utils::{
+        commitment::{Commitment, Opening},
+        config::{PeerConfig, RandomMode},
+        errors::{CommitmentError, TranscriptError},
+        extraction::{extract_random, OUTPUT_LENGTH},
+        identity::{commitment_message, reveal_message, verify_signature},
+    },
+};
+
+fn find_opening(proof: &SessionProof, node_id: u16) -> Result<&RevealedRandom, TranscriptError> {
+    proof
+        .openings
+        .iter()
+        .find(|revealed| revealed.node_id == node_id)
+        .ok_or(TranscriptError::MissingOpening(node_id))
+}
+
+fn decode(revealed: &RevealedRandom) -> Result<(Commitment, Opening), TranscriptError> {
+    let commitment = Commitment::from_slice(&revealed.commitment)
+        .map_err(|_error| TranscriptError::InvalidCommitment(revealed.node_id))?;
+    let opening = Opening::from_slice(&revealed.opening)
+        .map_err(|_error| TranscriptError::InvalidOpening(revealed.node_id))?;
+    Ok((commitment, opening))
+}
+
+// every participant has to have recorded the commitment it returned during co-commitment
+fn find_commitment(
+    proof: &SessionProof,
+    node_id: u16,
+) -> Result<&CommitmentForRandom, TranscriptError> {
+    proof
+        .commitments
+        .iter()
+        .find(|recorded| recorded.node_id == node_id)
+        .ok_or(TranscriptError::MissingCommitment(node_id))
+}
+
+// revealed commitment has to be the one the node returned during co-commitment
+fn verify_recorded_commitment(
+    proof: &SessionProof,
+    revealed: &RevealedRandom,
+) -> Result<(), TranscriptError> {
+    let recorded = find_commitment(proof, revealed.node_id)?;
+    if recorded.commitment != revealed.commitment {
+        return Err(TranscriptError::CommitmentMismatch(revealed.node_id));
+    }
+    Ok(())
+}
+
+// recorded commitments carry a proof of knowledge of the opening and, in integer mode, a range
+// proof, which have to hold before aggregation, the mode of the session is taken from the transcript
+fn verify_recorded_proofs(
+    proof: &SessionProof,
+    node_id: u16,
+    commitment: &Commitment,
+    opening: &Opening,
+) -> Result<(), TranscriptError> {
+    let recorded = find_commitment(proof, node_id)?;
+    if recorded.random_mode != proof.random_mode || opening.value.mode() != proof.random_mode {
+        return Err(TranscriptError::MixedModes(node_id));
+    }
+
+    if !commitment.verify_knowledge(&recorded.knowledge_proof, proof.commitment_id, node_id) {
+        return Err(TranscriptError::InvalidKnowledgeProof(node_id));
+    }
+
+    if proof.random_mode == RandomMode::Integer
+        && !commitment.verify_range(&recorded.range_proof, proof.commitment_id, node_id)
+    {
+        return Err(TranscriptError::InvalidRangeProof(node_id));
+    }
+
+    Ok(())
+}
+
+/// Verifies a session transcript offline and returns the aggregated opening.
+///
+/// Every opening is checked against its node's commitment (peers co-commitments include the
+/// dealer commitment, which is subtracted), the dealer overcommitment is removed from the sum of
+/// all the commitments and the aggregated opening is checked against the aggregated commitment.
+/// Every participant needs a recorded commitment in the mode of the session, whose proof of
+/// knowledge and, in integer mode, range proof are checked.
+/// The final random and the extracted output are checked when present in the transcript.
+pub fn verify_transcript(proof: &SessionProof) -> Result<Opening, TranscriptError> {
+    let aggregated_commitment = Commitment::from_slice(&proof.commitment)
+        .map_err(|_error| TranscriptError::InvalidAggregatedCommitment)?;
+
+    if !proof.node_ids.contains(&proof.dealer_id) {
+        return Err(TranscriptError::MissingDealer(proof.dealer_id));
+    }
+
+    // a participant listed twice would have its contribution counted twice
+    let mut participants = HashSet::new();
+    if let Some(node_id) = proof
+        .node_ids
+        .iter()
+        .find(|node_id| !participants.insert(**node_id))
+    {
+        return Err(TranscriptError::DuplicateParticipant(*node_id));
+    }
+
+    let mut seen = HashSet::new();
+    for revealed in &proof.openings {
+        if !proof.node_ids.contains(&revealed.node_id) || !seen.insert(revealed.node_id) {
+            return Err(TranscriptError::UnexpectedOpening(revealed.node_id));
+        }
+    }
+
+    let dealer = find_opening(proof, proof.dealer_id)?;
+    verify_recorded_commitment(proof, dealer)?;
+    let (dealer_commitment, dealer_opening) = decode(dealer)?;
+    if !dealer_commitment.verify(&dealer_opening) {
+        return Err(TranscriptError::OpeningMismatch(proof.dealer_id));
+    }
+    verify_recorded_proofs(proof, proof.dealer_id, &dealer_commitment, &dealer_opening)?;
+
+    let mut commitments = dealer_commitment.clone();
+    let mut aggregated_opening = dealer_opening;
+    for node_id in &proof.node_ids {
+        if *node_id == proof.dealer_id {
+            continue;
+        }
+
+        let revealed = find_opening(proof, *node_id)?;
+        verify_recorded_commitment(proof, revealed)?;
+        let (co_commitment, opening) = decode(revealed)?;
+
+        // remove dealer overcommitment
+        let commitment = &co_commitment - &dealer_commitment;
+        if !commitment.verify(&opening) {
+            return Err(TranscriptError::OpeningMismatch(*node_id));
+        }
+        verify_recorded_proofs(proof, *node_id, &commitment, &opening)?;
+
+        commitments = commitments + co_commitment - dealer_commitment.clone();
+        aggregated_opening =
+            aggregated_opening
+                .checked_add(&opening)
+                .map_err(|error| match error {
+                    CommitmentError::MixedModes => TranscriptError::MixedModes(*node_id),
+                    _ => TranscriptError::OpeningOverflow(*node_id),
+                })?;
+    }
+
+    if commitments != aggregated_commitment {
+        return Err(TranscriptError::AggregatedCommitmentMismatch);
+    }
+
+    if !aggregated_commitment.verify(&aggregated_opening) {
+        return Err(TranscriptError::AggregatedOpeningMismatch);
+    }
+
+    if let Some(random) = proof.random {
+        if random != aggregated_opening.value {
+            return Err(TranscriptError::RandomMismatch);
+        }
+    }
+
+    if let Some(output) = &proof.output {
+        let extracted = extract_random(
+            &aggregated_opening,
+            proof.commitment_id,
+            &proof.node_ids,
+            OUTPUT_LENGTH,
+        );
+        if *output != extracted {
+            return Err(TranscriptError::OutputMismatch);
+        }
+    }
+
+    Ok(aggregated_opening)
+}
+
+/// Verifies the node signatures of a session transcript against the peer table.
+///
+/// Every participant has to be in the table. Once a node of the table has a public key, every
+/// recorded commitment and revealed opening has to be signed by its node, so the transcript proves
+/// which node committed to and revealed what. Tables without any public key verify nothing.
+pub fn verify_transcript_signatures(
+    proof: &SessionProof,
+    peers: &[PeerConfig],
+) -> Result<(), TranscriptError> {
+    let signed = peers.iter().any(|peer| peer.public_key.is_some());
+    let public_key = |node_id: u16| {
+        let peer = peers
+            .iter()
+            .find(|peer| peer.id == node_id)
+            .ok_or(TranscriptError::UnknownNode(node_id))?;
+        match peer.decode_public_key() {
+            Ok(Some(public_key)) => Ok(Some(public_key)),
+            Ok(None) if signed => Err(TranscriptError::MissingPublicKey(node_id)),
+            Ok(None) => Ok(None),
+            Err(_) => Err(TranscriptError::InvalidSignature(node_id)),
+        }
+    };
+
+    for node_id in &proof.node_ids {
+        public_key(*node_id)?;
+    }
+
+    for recorded in &proof.commitments {
+        if let Some(public_key) = public_key(recorded.node_id)? {
+            if !verify_signature(
+                &public_key,
+                &commitment_message(recorded),
+                &recorded.signature,
+            ) {
+                return Err(TranscriptError::InvalidSignature(recorded.node_id));
+            }
+        }
+    }
+
+    for revealed in &proof.openings {
+        if let Some(public_key) = public_key(revealed.node_id)? {
+            // the dealer reveals its own entry, the peers their co-commitments
+            let role = if revealed.node_id == proof.dealer_id {
+                Role::Dealer
+            } else {
+                Role::CoCommitter
+            };
+            let message = reveal_message(
+                revealed.node_id,
+                proof.commitment_id,
+                role,
+                &revealed.commitment,
+                &revealed.opening,
+            );
+            if !verify_signature(&public_key, &message, &revealed.signature) {
+                return Err(TranscriptError::InvalidSignature(revealed.node_id));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use curve25519_dalek_ng::scalar::Scalar;
+
+    use crate::utils::{commitment::OpeningValue, identity::NodeIdentity};
+
+    use super::*;
+
+    // builds the transcript of a draw between a dealer (node 1) and two peers
+    fn create_proof() -> SessionProof {
+        let commitment_id = 123_u128;
+        let (dealer_commitment, dealer_opening) = Commitment::new(1000);
+        let mut aggregated_commitment = dealer_commitment.clone();
+        let mut commitments = vec![CommitmentForRandom::for_test(
+            1,
+            commitment_id,
+            &dealer_commitment,
+            &dealer_opening,
+        )];
+        let mut openings = vec![RevealedRandom {
+            node_id: 1,
+            commitment: dealer_commitment.to_bytes(),
+            opening: dealer_opening.to_bytes(),
+            signature: Vec::new(),
+        }];
+
+        for (node_id, value) in [(2, 500), (3, 250)] {
+            let (commitment, opening) = Commitment::new(value);
+            let co_commitment = commitment.clone() + dealer_commitment.clone();
+            aggregated_commitment = aggregated_commitment + commitment;
+            commitments.push(CommitmentForRandom::for_test(
+                node_id,
+                commitment_id,
+                &co_commitment,
+                &opening,
+            ));
+            openings.push(RevealedRandom {
+                node_id,
+                commitment: co_commitment.to_bytes(),
+                opening: opening.to_bytes(),
+                signature: Vec::new(),
+            });
+        }
+
+        SessionProof {
+            commitment_id,
+            dealer_id: 1,
+            node_ids: vec![2, 3, 1],
+            commitment: aggregated_commitment.to_bytes(),
+            commitments,
+            openings,
+            random: Some(OpeningValue::Integer(1750)),
+            output: None,
+            random_mode: RandomMode::Integer,
+        }
+    }
+
+    #[test]
+    fn test_verify_transcript() {
+        let mut proof = create_proof();
+        let opening = verify_transcript(&proof).unwrap();
+        assert_eq!(opening.value, OpeningValue::Integer(1750));
+
+        proof.output = Some(extract_random(
+            &opening,
+            proof.commitment_id,
+            &proof.node_ids,
+            OUTPUT_LENGTH,
+        ));
+        assert_eq!(verify_transcript(&proof), Ok(opening));
+    }
+
+    #[test]
+    fn test_verify_transcript_missing_opening() {
+        let mut proof = create_proof();
+        proof.openings.retain(|revealed| revealed.node_id != 3);
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::MissingOpening(3))
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_tampered_opening() {
+        let mut proof = create_proof();
+        let (_, opening) = Commitment::new(500);
+        proof.openings[1].opening = opening.to_bytes();
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::OpeningMismatch(2))
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_substituted_commitment() {
+        let mut proof = create_proof();
+        let (commitment, opening) = Commitment::new(1);
+        proof.openings[2].commitment = commitment.to_bytes();
+        proof.openings[2].opening = opening.to_bytes();
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::CommitmentMismatch(3))
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_invalid_range_proof() {
+        let mut proof = create_proof();
+        proof.commitments[2].range_proof = proof.commitments[1].range_proof.clone();
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::InvalidRangeProof(3))
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_invalid_knowledge_proof() {
+        let mut proof = create_proof();
+        proof.commitments[1].knowledge_proof = proof.commitments[2].knowledge_proof.clone();
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::InvalidKnowledgeProof(2))
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_invalid_opening() {
+        let mut proof = create_proof();
+        proof.openings[1].opening[8..].copy_from_slice(&[0xff_u8; 32]);
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::InvalidOpening(2))
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_missing_commitment() {
+        let mut proof = create_proof();
+        proof.commitments.retain(|recorded| recorded.node_id != 2);
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::MissingCommitment(2))
+        );
+
+        proof.commitments.clear();
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::MissingCommitment(1))
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_duplicate_participant() {
+        let mut proof = create_proof();
+        proof.node_ids.push(2);
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::DuplicateParticipant(2))
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_opening_overflow() {
+        let mut proof = create_proof();
+        for (index, value) in [(0, u64::MAX), (1, 1)] {
+            let node_id = index as u16 + 1;
+            let (commitment, opening) = Commitment::new(value);
+            proof.commitments[index].commitment = commitment.to_bytes();
+            proof.commitments[index].range_proof = opening
+                .prove_range(proof.commitment_id, node_id)
+                .unwrap_or_default();
+            proof.commitments[index].knowledge_proof = opening
+                .prove_knowledge(proof.commitment_id, node_id)
+                .unwrap();
+            proof.openings[index].commitment = commitment.to_bytes();
+            proof.openings[index].opening = opening.to_bytes();
+        }
+        // co-commitment of node 2 includes the dealer commitment
+        let dealer_commitment = Commitment::from_slice(&proof.openings[0].commitment).unwrap();
+        let commitment = Commitment::from_slice(&proof.openings[1].commitment).unwrap();
+        proof.openings[1].commitment = (commitment + dealer_commitment).to_bytes();
+        proof.commitments[1].commitment = proof.openings[1].commitment.clone();
+        proof.node_ids = vec![1, 2];
+        proof.openings.truncate(2);
+        proof.commitments.truncate(2);
+        // contributions that could overflow the aggregation can't be proven in range
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::InvalidRangeProof(1))
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_mixed_modes() {
+        let mut proof = create_proof();
+        let dealer_commitment = Commitment::from_slice(&proof.openings[0].commitment).unwrap();
+        let (commitment, opening) = Commitment::new_scalar(Scalar::from(500_u64));
+        let co_commitment = commitment + dealer_commitment;
+        proof.commitments[1].commitment = co_commitment.to_bytes();
+        proof.commitments[1].knowledge_proof =
+            opening.prove_knowledge(proof.commitment_id, 2).unwrap();
+        proof.openings[1].commitment = co_commitment.to_bytes();
+        proof.openings[1].opening = opening.to_bytes();
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::MixedModes(2))
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_session_mode() {
+        // the range proofs of an integer session can't be skipped by recording it as scalar
+        let mut proof = create_proof();
+        proof.random_mode = RandomMode::Scalar;
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::MixedModes(1))
+        );
+
+        let mut proof = create_proof();
+        proof.commitments[2].random_mode = RandomMode::Scalar;
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::MixedModes(3))
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_wrong_aggregate() {
+        let mut proof = create_proof();
+        let (commitment, _) = Commitment::new(1750);
+        proof.commitment = commitment.to_bytes();
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::AggregatedCommitmentMismatch)
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_wrong_random() {
+        let mut proof = create_proof();
+        proof.random = Some(OpeningValue::Integer(1751));
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::RandomMismatch)
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_wrong_output() {
+        let mut proof = create_proof();
+        proof.output = Some(vec![0_u8; OUTPUT_LENGTH]);
+        assert_eq!(
+            verify_transcript(&proof),
+            Err(TranscriptError::OutputMismatch)
+        );
+    }
+
+    #[test]
+    fn test_verify_transcript_signatures() {
+        let mut proof = create_proof();
+        let mut peers = Vec::new();
+        for node_id in [1, 2, 3] {
+            let identity = NodeIdentity::from_pkcs8(&NodeIdentity::generate().unwrap()).unwrap();
+            let recorded = &mut proof.commitments[node_id as usize - 1];
+            recorded.signature = identity.sign(&commitment_message(recorded));
+            let revealed = &mut proof.openings[node_id as usize - 1];
+            let role = match node_id {
+                1 => Role::Dealer,
+                _ => Role::CoCommitter,
+            };
+            revealed.signature = identity.sign(&reveal_message(
+                node_id,
+                proof.commitment_id,
+                role,
+                &revealed.commitment,
+                &revealed.opening,
+            ));
+            peers.push(PeerConfig {
+                id: node_id,
+                url: format!("http://node-{}:7000", node_id),
+                public_key: Some(hex::encode(identity.public_key())),
+                peer_url: None,
+            });
+        }
+        assert_eq!(verify_transcript_signatures(&proof, &peers), Ok(()));
+
+        // the signature of node 2 doesn't cover the reveal of node 3
+        proof.openings[2].signature = proof.openings[1].signature.clone();
+        assert_eq!(
+            verify_transcript_signatures(&proof, &peers),
+            Err(TranscriptError::InvalidSignature(3))
+        );
+
+        // once a node has a public key, every node has to sign
+        proof.openings[2].signature = Vec::new();
+        peers[2].public_key = None;
+        assert_eq!(
+            verify_transcript_signatures(&proof, &peers),
+            Err(TranscriptError::MissingPublicKey(3))
+        );
+
+        // participants outside of the peer table are rejected
+        peers.pop();
+        assert_eq!(
+            verify_transcript_signatures(&proof, &peers),
+            Err(TranscriptError::UnknownNode(3))
+        );
+
+        // tables without any public key verify nothing
+        for peer in &mut peers {
+            peer.public_key = None;
+        }
+        peers.push(PeerConfig {
+            id: 3,
+            url: "http://node-3:7000".to_string(),
+            public_key: None,
+            peer_url: None,
+        });
+        assert_eq!(verify_transcript_signatures(&proof, &peers), Ok(()));
+    }
+}